@@ -0,0 +1,65 @@
+-- THORChain swap tables written by the Midgard sync. `date` is stored as YYYY-MM-DD text and
+-- `timestamp` in unix seconds. Tables added later through sources.toml use the same DDL, and
+-- need the columns added by the later alter_thorchain_swaps migrations.
+CREATE TABLE IF NOT EXISTS native_swaps_thorchain (
+    id SERIAL PRIMARY KEY,
+    timestamp BIGINT NOT NULL,
+    date VARCHAR(10) NOT NULL,
+    time VARCHAR(16) NOT NULL,
+    tx_id VARCHAR(255) NOT NULL UNIQUE,
+    in_asset VARCHAR(255) NOT NULL,
+    in_amount DOUBLE PRECISION NOT NULL,
+    in_address VARCHAR(255) NOT NULL,
+    out_asset_1 VARCHAR(255) NOT NULL,
+    out_amount_1 DOUBLE PRECISION NOT NULL,
+    out_address_1 VARCHAR(255) NOT NULL,
+    out_asset_2 VARCHAR(255),
+    out_amount_2 DOUBLE PRECISION,
+    out_address_2 VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS swap_history_test (
+    id SERIAL PRIMARY KEY,
+    timestamp BIGINT NOT NULL,
+    date VARCHAR(10) NOT NULL,
+    time VARCHAR(16) NOT NULL,
+    tx_id VARCHAR(255) NOT NULL UNIQUE,
+    in_asset VARCHAR(255) NOT NULL,
+    in_amount DOUBLE PRECISION NOT NULL,
+    in_address VARCHAR(255) NOT NULL,
+    out_asset_1 VARCHAR(255) NOT NULL,
+    out_amount_1 DOUBLE PRECISION NOT NULL,
+    out_address_1 VARCHAR(255) NOT NULL,
+    out_asset_2 VARCHAR(255),
+    out_amount_2 DOUBLE PRECISION,
+    out_address_2 VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Historical BTC swaps served read-only by /swaps
+CREATE TABLE IF NOT EXISTS btc_user_data (
+    id SERIAL PRIMARY KEY,
+    timestamp BIGINT NOT NULL,
+    date VARCHAR(10) NOT NULL,
+    time VARCHAR(16) NOT NULL,
+    tx_id VARCHAR(255) NOT NULL UNIQUE,
+    in_asset VARCHAR(255) NOT NULL,
+    in_amount DOUBLE PRECISION NOT NULL,
+    in_address VARCHAR(255) NOT NULL,
+    out_asset_1 VARCHAR(255) NOT NULL,
+    out_amount_1 DOUBLE PRECISION NOT NULL,
+    out_address_1 VARCHAR(255) NOT NULL,
+    out_asset_2 VARCHAR(255),
+    out_amount_2 DOUBLE PRECISION,
+    out_address_2 VARCHAR(255),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes
+CREATE INDEX IF NOT EXISTS native_swaps_thorchain_timestamp_idx ON native_swaps_thorchain (timestamp, tx_id);
+CREATE INDEX IF NOT EXISTS native_swaps_thorchain_date_idx ON native_swaps_thorchain (date);
+CREATE INDEX IF NOT EXISTS swap_history_test_timestamp_idx ON swap_history_test (timestamp, tx_id);
+CREATE INDEX IF NOT EXISTS swap_history_test_date_idx ON swap_history_test (date);
+CREATE INDEX IF NOT EXISTS btc_user_data_timestamp_idx ON btc_user_data (timestamp, tx_id);
+CREATE INDEX IF NOT EXISTS btc_user_data_date_idx ON btc_user_data (date);
@@ -0,0 +1,5 @@
+-- Daily BTC closing prices in USD used to value THORChain swaps. `date` is YYYY-MM-DD text.
+CREATE TABLE IF NOT EXISTS btc_closing_prices (
+    date VARCHAR(10) PRIMARY KEY,
+    closing_price_usd DOUBLE PRECISION NOT NULL
+);
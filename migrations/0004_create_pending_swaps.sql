@@ -0,0 +1,13 @@
+-- Create pending_swaps table used to retry THORChain swaps that were not yet settled
+CREATE TABLE IF NOT EXISTS pending_swaps (
+    tx_id VARCHAR(255) NOT NULL,
+    swap_type VARCHAR(32) NOT NULL,
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_attempt_at TIMESTAMP WITH TIME ZONE,
+    attempt_count INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    PRIMARY KEY (tx_id, swap_type)
+);
+
+-- Create indexes
+CREATE INDEX IF NOT EXISTS pending_swaps_swap_type_idx ON pending_swaps (swap_type);
@@ -0,0 +1,15 @@
+-- Create dead_letter_swaps table holding pending swaps that were abandoned by the retry job
+CREATE TABLE IF NOT EXISTS dead_letter_swaps (
+    tx_id VARCHAR(255) NOT NULL,
+    swap_type VARCHAR(32) NOT NULL,
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    last_attempt_at TIMESTAMP WITH TIME ZONE,
+    attempt_count INTEGER NOT NULL,
+    last_error TEXT,
+    reason TEXT NOT NULL,
+    dead_lettered_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (tx_id, swap_type)
+);
+
+-- Create indexes
+CREATE INDEX IF NOT EXISTS dead_letter_swaps_swap_type_idx ON dead_letter_swaps (swap_type);
@@ -0,0 +1,20 @@
+-- Key pending and dead-lettered swaps by the configured source (see sources.toml)
+ALTER TABLE pending_swaps ADD COLUMN IF NOT EXISTS source VARCHAR(64);
+UPDATE pending_swaps
+SET source = CASE swap_type WHEN 'NATIVE' THEN 'btc-native' ELSE 'btc-trade' END
+WHERE source IS NULL;
+ALTER TABLE pending_swaps ALTER COLUMN source SET NOT NULL;
+ALTER TABLE pending_swaps DROP CONSTRAINT IF EXISTS pending_swaps_pkey;
+ALTER TABLE pending_swaps ADD PRIMARY KEY (tx_id, source);
+
+ALTER TABLE dead_letter_swaps ADD COLUMN IF NOT EXISTS source VARCHAR(64);
+UPDATE dead_letter_swaps
+SET source = CASE swap_type WHEN 'NATIVE' THEN 'btc-native' ELSE 'btc-trade' END
+WHERE source IS NULL;
+ALTER TABLE dead_letter_swaps ALTER COLUMN source SET NOT NULL;
+ALTER TABLE dead_letter_swaps DROP CONSTRAINT IF EXISTS dead_letter_swaps_pkey;
+ALTER TABLE dead_letter_swaps ADD PRIMARY KEY (tx_id, source);
+
+-- Create indexes
+CREATE INDEX IF NOT EXISTS pending_swaps_source_idx ON pending_swaps (source);
+CREATE INDEX IF NOT EXISTS dead_letter_swaps_source_idx ON dead_letter_swaps (source);
@@ -0,0 +1,23 @@
+-- Add Midgard swap metadata (fees, slippage, affiliate, streaming) to the THORChain swap tables.
+-- Tables added later through sources.toml need the same columns.
+ALTER TABLE IF EXISTS native_swaps_thorchain
+    ADD COLUMN IF NOT EXISTS network_fees JSONB,
+    ADD COLUMN IF NOT EXISTS liquidity_fee DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS swap_slip_bps BIGINT,
+    ADD COLUMN IF NOT EXISTS affiliate_fee_bps BIGINT,
+    ADD COLUMN IF NOT EXISTS affiliate_address VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS memo TEXT,
+    ADD COLUMN IF NOT EXISTS is_streaming_swap BOOLEAN,
+    ADD COLUMN IF NOT EXISTS streaming_quantity BIGINT,
+    ADD COLUMN IF NOT EXISTS streaming_interval BIGINT;
+
+ALTER TABLE IF EXISTS swap_history_test
+    ADD COLUMN IF NOT EXISTS network_fees JSONB,
+    ADD COLUMN IF NOT EXISTS liquidity_fee DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS swap_slip_bps BIGINT,
+    ADD COLUMN IF NOT EXISTS affiliate_fee_bps BIGINT,
+    ADD COLUMN IF NOT EXISTS affiliate_address VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS memo TEXT,
+    ADD COLUMN IF NOT EXISTS is_streaming_swap BOOLEAN,
+    ADD COLUMN IF NOT EXISTS streaming_quantity BIGINT,
+    ADD COLUMN IF NOT EXISTS streaming_interval BIGINT;
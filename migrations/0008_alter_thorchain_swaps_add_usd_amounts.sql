@@ -0,0 +1,9 @@
+-- Store USD values of THORChain swaps using Midgard's inPriceUSD/outPriceUSD metadata.
+-- Tables added later through sources.toml need the same columns.
+ALTER TABLE IF EXISTS native_swaps_thorchain
+    ADD COLUMN IF NOT EXISTS in_amount_usd DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS out_amount_usd DOUBLE PRECISION;
+
+ALTER TABLE IF EXISTS swap_history_test
+    ADD COLUMN IF NOT EXISTS in_amount_usd DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS out_amount_usd DOUBLE PRECISION;
@@ -0,0 +1,8 @@
+-- Indexes backing the /addresses/{address} lookups through the unified_swaps view.
+-- Tables added later through sources.toml need the same indexes.
+CREATE INDEX IF NOT EXISTS native_swaps_thorchain_in_address_idx ON native_swaps_thorchain (in_address);
+CREATE INDEX IF NOT EXISTS native_swaps_thorchain_out_address_1_idx ON native_swaps_thorchain (out_address_1);
+CREATE INDEX IF NOT EXISTS swap_history_test_in_address_idx ON swap_history_test (in_address);
+CREATE INDEX IF NOT EXISTS swap_history_test_out_address_1_idx ON swap_history_test (out_address_1);
+CREATE INDEX IF NOT EXISTS chainflip_swaps_detailed_destination_address_idx ON chainflip_swaps_detailed (destination_address);
+CREATE INDEX IF NOT EXISTS chainflip_swaps_detailed_refund_address_idx ON chainflip_swaps_detailed (refund_address);
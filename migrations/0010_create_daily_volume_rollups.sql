@@ -0,0 +1,14 @@
+-- Per-day, per-protocol swap volume maintained by the volume rollup job from unified_swaps.
+CREATE TABLE IF NOT EXISTS daily_volume_rollups (
+    date DATE NOT NULL,
+    protocol VARCHAR(32) NOT NULL,
+    swap_count BIGINT NOT NULL,
+    unique_addresses BIGINT NOT NULL,
+    volume_usd DOUBLE PRECISION,
+    btc_volume DOUBLE PRECISION NOT NULL,
+    avg_size_usd DOUBLE PRECISION,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (date, protocol)
+);
+
+CREATE INDEX IF NOT EXISTS daily_volume_rollups_protocol_idx ON daily_volume_rollups (protocol);
@@ -0,0 +1,12 @@
+-- BTC closing price (from btc_closing_prices) for the swap's date and the USD value of the swap's BTC leg.
+-- Filled in by the swap valuation job. Tables added later through sources.toml need the same columns.
+ALTER TABLE IF EXISTS native_swaps_thorchain
+    ADD COLUMN IF NOT EXISTS btc_price_usd DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS btc_value_usd DOUBLE PRECISION;
+
+ALTER TABLE IF EXISTS swap_history_test
+    ADD COLUMN IF NOT EXISTS btc_price_usd DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS btc_value_usd DOUBLE PRECISION;
+
+ALTER TABLE IF EXISTS daily_volume_rollups
+    ADD COLUMN IF NOT EXISTS btc_volume_usd DOUBLE PRECISION;
@@ -0,0 +1,54 @@
+-- Normalized view over THORChain and Chainflip swaps.
+-- Tables added later through sources.toml need to be added to this view.
+-- THORChain USD amounts fall back to the BTC closing price when Midgard had no price.
+CREATE OR REPLACE VIEW unified_swaps AS
+SELECT
+    'thorchain' AS protocol,
+    tx_id AS swap_id,
+    in_asset AS source_asset,
+    out_asset_1 AS dest_asset,
+    in_amount AS source_amount,
+    out_amount_1 AS dest_amount,
+    COALESCE(in_amount_usd, CASE WHEN in_asset IN ('BTC.BTC', 'BTC~BTC') THEN in_amount * btc_price_usd END) AS source_amount_usd,
+    COALESCE(out_amount_usd, CASE WHEN out_asset_1 IN ('BTC.BTC', 'BTC~BTC') THEN out_amount_1 * btc_price_usd END) AS dest_amount_usd,
+    in_address AS source_address,
+    out_address_1 AS dest_address,
+    CAST(timestamp AS BIGINT) AS timestamp,
+    'success' AS status,
+    btc_value_usd
+FROM native_swaps_thorchain
+UNION ALL
+SELECT
+    'thorchain' AS protocol,
+    tx_id AS swap_id,
+    in_asset AS source_asset,
+    out_asset_1 AS dest_asset,
+    in_amount AS source_amount,
+    out_amount_1 AS dest_amount,
+    COALESCE(in_amount_usd, CASE WHEN in_asset IN ('BTC.BTC', 'BTC~BTC') THEN in_amount * btc_price_usd END) AS source_amount_usd,
+    COALESCE(out_amount_usd, CASE WHEN out_asset_1 IN ('BTC.BTC', 'BTC~BTC') THEN out_amount_1 * btc_price_usd END) AS dest_amount_usd,
+    in_address AS source_address,
+    out_address_1 AS dest_address,
+    CAST(timestamp AS BIGINT) AS timestamp,
+    'success' AS status,
+    btc_value_usd
+FROM swap_history_test
+UNION ALL
+SELECT
+    'chainflip' AS protocol,
+    swap_id,
+    source_asset,
+    dest_asset,
+    input_amount AS source_amount,
+    output_amount AS dest_amount,
+    input_value_usd AS source_amount_usd,
+    output_value_usd AS dest_amount_usd,
+    refund_address AS source_address,
+    destination_address AS dest_address,
+    CAST(timestamp AS BIGINT) AS timestamp,
+    LOWER(status) AS status,
+    CASE
+        WHEN source_asset = 'BTC' THEN input_value_usd
+        WHEN dest_asset = 'BTC' THEN output_value_usd
+    END AS btc_value_usd
+FROM chainflip_swaps_detailed;
@@ -0,0 +1,27 @@
+-- Daily USD closing prices for every asset seen in the swap tables, keyed by the
+-- chain-independent symbol from utils::price_symbol (e.g. ETH.USDC-0X... and ARBUSDC -> USDC).
+CREATE TABLE IF NOT EXISTS closing_prices (
+    asset VARCHAR(64) NOT NULL,
+    date DATE NOT NULL,
+    coin_id VARCHAR(255) NOT NULL,
+    closing_price_usd DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (asset, date)
+);
+
+-- CoinGecko ID for each symbol as resolved through /search. A NULL coin_id records that the
+-- search found nothing, so the symbol isn't searched again; delete or edit rows to remap.
+CREATE TABLE IF NOT EXISTS coingecko_coin_ids (
+    asset VARCHAR(64) PRIMARY KEY,
+    coin_id VARCHAR(255),
+    resolved_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO coingecko_coin_ids (asset, coin_id)
+VALUES ('BTC', 'bitcoin')
+ON CONFLICT (asset) DO NOTHING;
+
+INSERT INTO closing_prices (asset, date, coin_id, closing_price_usd)
+SELECT 'BTC', CAST(date AS DATE), 'bitcoin', closing_price_usd
+FROM btc_closing_prices
+ON CONFLICT (asset, date) DO NOTHING;
@@ -0,0 +1,39 @@
+-- CoinGecko's /history?date=D returns the price at 00:00 UTC on D, which is the close of D - 1,
+-- but it used to be stored under D. price_at records the snapshot a row was taken from; rows
+-- without it are re-keyed to the day they actually close. Rows whose close is unknown are
+-- dropped and refetched by the startup backfill. Safe to re-run: only rows without price_at change.
+ALTER TABLE IF EXISTS btc_closing_prices
+    ADD COLUMN IF NOT EXISTS price_at TIMESTAMP WITH TIME ZONE;
+
+ALTER TABLE IF EXISTS closing_prices
+    ADD COLUMN IF NOT EXISTS price_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE btc_closing_prices AS prices
+SET
+    closing_price_usd = next_day.closing_price_usd,
+    price_at = CAST(CAST(prices.date AS DATE) + 1 AS TIMESTAMP) AT TIME ZONE 'UTC'
+FROM btc_closing_prices AS next_day
+WHERE prices.price_at IS NULL
+    AND next_day.price_at IS NULL
+    AND CAST(next_day.date AS DATE) = CAST(prices.date AS DATE) + 1;
+
+DELETE FROM btc_closing_prices WHERE price_at IS NULL;
+
+UPDATE closing_prices AS prices
+SET
+    closing_price_usd = next_day.closing_price_usd,
+    price_at = CAST(prices.date + 1 AS TIMESTAMP) AT TIME ZONE 'UTC'
+FROM closing_prices AS next_day
+WHERE prices.price_at IS NULL
+    AND next_day.price_at IS NULL
+    AND next_day.asset = prices.asset
+    AND next_day.date = prices.date + 1;
+
+DELETE FROM closing_prices WHERE price_at IS NULL;
+
+-- Swaps valued with the old prices are revalued by the swap valuation job
+UPDATE native_swaps_thorchain SET btc_price_usd = NULL, btc_value_usd = NULL
+WHERE btc_value_usd IS NOT NULL;
+
+UPDATE swap_history_test SET btc_price_usd = NULL, btc_value_usd = NULL
+WHERE btc_value_usd IS NOT NULL;
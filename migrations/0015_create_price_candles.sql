@@ -0,0 +1,12 @@
+-- Hourly OHLC candles built from CoinGecko market_chart/range, keyed by the same asset symbol
+-- as closing_prices. Filled by the price candle job when PRICE_CANDLES_ENABLED=true.
+CREATE TABLE IF NOT EXISTS price_candles (
+    asset VARCHAR(64) NOT NULL,
+    open_time TIMESTAMP WITH TIME ZONE NOT NULL,
+    open DOUBLE PRECISION NOT NULL,
+    high DOUBLE PRECISION NOT NULL,
+    low DOUBLE PRECISION NOT NULL,
+    close DOUBLE PRECISION NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (asset, open_time)
+);
@@ -0,0 +1,11 @@
+-- Records which PriceProvider (coingecko, midgard or chainflip) supplied each stored price.
+-- Prices stored before providers existed all came from CoinGecko.
+ALTER TABLE IF EXISTS btc_closing_prices
+    ADD COLUMN IF NOT EXISTS provider VARCHAR(32) NOT NULL DEFAULT 'coingecko';
+
+ALTER TABLE IF EXISTS closing_prices
+    ADD COLUMN IF NOT EXISTS provider VARCHAR(32) NOT NULL DEFAULT 'coingecko';
+
+-- Midgard and Chainflip prices have no CoinGecko id
+ALTER TABLE IF EXISTS closing_prices
+    ALTER COLUMN coin_id DROP NOT NULL;
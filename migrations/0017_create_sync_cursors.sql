@@ -0,0 +1,11 @@
+-- Where each source's sync left off, replacing next_page_token.txt. `direction` is backward
+-- for history walks using nextPageToken and forward for the incremental poll. Backward
+-- cursors are written in the same transaction as the batch they cover.
+CREATE TABLE IF NOT EXISTS sync_cursors (
+    source VARCHAR(64) NOT NULL,
+    direction VARCHAR(16) NOT NULL,
+    token VARCHAR(255),
+    timestamp BIGINT,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (source, direction)
+);
@@ -0,0 +1,7 @@
+-- btc_user_data is served by /swaps with the same USD and BTC valuation columns as the synced
+-- tables. The swap valuation job fills btc_price_usd and btc_value_usd.
+ALTER TABLE IF EXISTS btc_user_data
+    ADD COLUMN IF NOT EXISTS in_amount_usd DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS out_amount_usd DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS btc_price_usd DOUBLE PRECISION,
+    ADD COLUMN IF NOT EXISTS btc_value_usd DOUBLE PRECISION;
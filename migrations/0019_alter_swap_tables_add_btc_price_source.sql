@@ -0,0 +1,20 @@
+-- Which BTC price the valuation job used for btc_price_usd: 'candle' for the hourly candle the swap
+-- executed in, 'close' for the day's closing price. Close-priced swaps are revalued once a candle
+-- for their hour is stored. Rows valued before this column existed are treated as close-priced.
+ALTER TABLE IF EXISTS native_swaps_thorchain
+    ADD COLUMN IF NOT EXISTS btc_price_source VARCHAR(16);
+
+ALTER TABLE IF EXISTS swap_history_test
+    ADD COLUMN IF NOT EXISTS btc_price_source VARCHAR(16);
+
+ALTER TABLE IF EXISTS btc_user_data
+    ADD COLUMN IF NOT EXISTS btc_price_source VARCHAR(16);
+
+UPDATE native_swaps_thorchain SET btc_price_source = 'close'
+WHERE btc_value_usd IS NOT NULL AND btc_price_source IS NULL;
+
+UPDATE swap_history_test SET btc_price_source = 'close'
+WHERE btc_value_usd IS NOT NULL AND btc_price_source IS NULL;
+
+UPDATE btc_user_data SET btc_price_source = 'close'
+WHERE btc_value_usd IS NOT NULL AND btc_price_source IS NULL;
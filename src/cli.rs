@@ -0,0 +1,333 @@
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use clap::{Args, Parser, Subcommand};
+use thiserror::Error;
+
+use crate::{
+    config::{PendingConfig, PricesConfig, SourceConfig, SourcesConfig},
+    db::PostgreSQL,
+    fetcher::{
+        backfill_btc_closing_prices, backfill_swaps, fetch_asset_closing_prices, fetch_daily_data,
+        fetch_price_candles, last_closed_date, refresh_volume_rollups, retry_pending_transactions,
+        BackfillOptions, PriceFetchSummary,
+    },
+    utils::{
+        coingecko::SharedCoinGecko,
+        midgard::MidGard,
+        pending_tracker::{PendingTracker, RetryPolicy},
+        price_provider::PriceProviderChain,
+        transaction_handler::TransactionError,
+    },
+};
+
+// Exit code 2 means the command was invoked wrongly, 1 that it ran and failed
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Usage(String),
+    #[error("{0}")]
+    Failed(String),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            CliError::Failed(_) => 1,
+        }
+    }
+}
+
+impl From<TransactionError> for CliError {
+    fn from(err: TransactionError) -> Self {
+        CliError::Failed(err.to_string())
+    }
+}
+
+// Without a subcommand the binary runs `serve` with its defaults
+#[derive(Debug, Parser)]
+#[command(
+    name = "swap-data-fetcher",
+    about = "THORChain and Chainflip swap fetcher"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the API server along with the background jobs
+    Serve(ServeArgs),
+    /// Fetch a source's swap history from Midgard, resuming from the last checkpoint
+    Backfill(BackfillArgs),
+    /// Refetch every swap from the start of a UTC day up to now
+    Reconcile(ReconcileArgs),
+    /// Retry pending swaps once
+    RetryPending(SourceArgs),
+    /// Fill missing closing prices, and hourly candles when enabled
+    FetchPrices(FetchPricesArgs),
+    /// Apply pending database migrations
+    Migrate,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Command::Serve(ServeArgs::default())
+    }
+}
+
+#[derive(Debug, Default, Args)]
+pub struct ServeArgs {
+    /// Serve the API without starting the background jobs
+    #[arg(long)]
+    pub no_workers: bool,
+    /// Address the API listens on; defaults to bind_address from the config
+    #[arg(long)]
+    pub bind: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct BackfillArgs {
+    /// Source name from sources.toml
+    #[arg(long)]
+    pub source: String,
+    /// Oldest swap timestamp to fetch, in unix seconds
+    #[arg(long)]
+    pub from: Option<i64>,
+    /// Newest swap timestamp to fetch, in unix seconds; ignored when resuming from a checkpoint
+    #[arg(long)]
+    pub to: Option<i64>,
+    /// Midgard pages inserted per transaction
+    #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+    pub batch_size: u32,
+    /// Ignore the saved checkpoint and start again from `--to`
+    #[arg(long)]
+    pub restart: bool,
+}
+
+impl BackfillArgs {
+    pub fn options(&self) -> Result<BackfillOptions, CliError> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(CliError::Usage(format!(
+                    "--from ({}) must not be after --to ({})",
+                    from, to
+                )));
+            }
+        }
+        Ok(BackfillOptions {
+            from: self.from,
+            to: self.to,
+            batch_size: self.batch_size as usize,
+            restart: self.restart,
+        })
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SourceArgs {
+    /// Source name from sources.toml; every source when omitted
+    #[arg(long)]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReconcileArgs {
+    /// UTC day to start from, as YYYY-MM-DD
+    #[arg(long)]
+    pub date: NaiveDate,
+    #[command(flatten)]
+    pub sources: SourceArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct FetchPricesArgs {
+    /// First day to backfill BTC closes for; defaults to prices.backfill_start
+    #[arg(long)]
+    pub from: Option<NaiveDate>,
+    /// Last day to backfill BTC closes for; defaults to yesterday
+    #[arg(long)]
+    pub to: Option<NaiveDate>,
+    /// Fetch hourly candles even if prices.candles_enabled is off
+    #[arg(long)]
+    pub candles: bool,
+}
+
+impl FetchPricesArgs {
+    pub fn range(
+        &self,
+        backfill_start: NaiveDate,
+        last_closed: NaiveDate,
+    ) -> Result<(NaiveDate, NaiveDate), CliError> {
+        let to = self.to.unwrap_or(last_closed);
+        let from = self.from.unwrap_or(backfill_start);
+        if to > last_closed {
+            return Err(CliError::Usage(format!(
+                "--to must be on or before {}",
+                last_closed
+            )));
+        }
+        if from > to {
+            return Err(CliError::Usage(format!(
+                "--from ({}) must not be after --to ({})",
+                from, to
+            )));
+        }
+        Ok((from, to))
+    }
+}
+
+pub fn select_sources<'a>(
+    sources: &'a SourcesConfig,
+    name: Option<&str>,
+) -> Result<Vec<&'a SourceConfig>, CliError> {
+    match name {
+        None => Ok(sources.sources.iter().collect()),
+        Some(name) => match sources.sources.iter().find(|source| source.name == name) {
+            Some(source) => Ok(vec![source]),
+            None => Err(CliError::Usage(format!("Unknown source {}", name))),
+        },
+    }
+}
+
+// Jobs keep going after a source fails and report every failure at the end
+fn check_failures(job: &str, failed: Vec<String>) -> Result<(), CliError> {
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(CliError::Failed(format!(
+            "{} failed for {}",
+            job,
+            failed.join(", ")
+        )))
+    }
+}
+
+// Rerunning a failed backfill resumes from the last committed batch
+pub async fn run_backfill(
+    pg: &PostgreSQL,
+    midgard: &MidGard,
+    sources: &SourcesConfig,
+    args: &BackfillArgs,
+) -> Result<(), CliError> {
+    let source = select_sources(sources, Some(&args.source))?[0];
+    let options = args.options()?;
+    let inserted = backfill_swaps(pg, midgard, source, &options)
+        .await
+        .map_err(|err| {
+            CliError::Failed(format!("Backfill of {} failed: {}", source.label(), err))
+        })?;
+    // The serve job only refreshes its trailing window, so cover the backfilled days here
+    if inserted > 0 {
+        let since = options
+            .from
+            .and_then(|from| DateTime::from_timestamp(from, 0))
+            .map(|from| from.date_naive());
+        refresh_volume_rollups(pg, since).await?;
+    }
+    Ok(())
+}
+
+pub async fn run_reconcile(
+    pg: &PostgreSQL,
+    midgard: &MidGard,
+    sources: &SourcesConfig,
+    args: &ReconcileArgs,
+) -> Result<(), CliError> {
+    let selected = select_sources(sources, args.sources.source.as_deref())?;
+    let day_start = args.date.and_time(NaiveTime::MIN).and_utc().timestamp();
+    let pending_tracker = PendingTracker::postgres(pg.clone());
+
+    let mut failed = Vec::new();
+    for source in selected {
+        println!("Reconciling {} from {}", source.label(), args.date);
+        if let Err(e) = fetch_daily_data(pg, midgard, &pending_tracker, source, day_start).await {
+            println!("Error reconciling {}: {}", source.label(), e);
+            failed.push(source.name.clone());
+        }
+    }
+    refresh_volume_rollups(pg, Some(args.date)).await?;
+    check_failures("reconcile", failed)
+}
+
+pub async fn run_retry_pending(
+    pg: &PostgreSQL,
+    midgard: &MidGard,
+    sources: &SourcesConfig,
+    config: &PendingConfig,
+    args: &SourceArgs,
+) -> Result<(), CliError> {
+    let selected = select_sources(sources, args.source.as_deref())?;
+    let pending_tracker = PendingTracker::postgres(pg.clone());
+    let retry_policy = RetryPolicy::new(config);
+
+    let mut failed = Vec::new();
+    for source in selected {
+        println!("Retrying Pending {} Transactions", source.label());
+        if let Err(e) =
+            retry_pending_transactions(pg, midgard, &pending_tracker, source, &retry_policy).await
+        {
+            println!(
+                "Error retrying pending {} transactions: {}",
+                source.label(),
+                e
+            );
+            failed.push(source.name.clone());
+        }
+    }
+    check_failures("retry-pending", failed)
+}
+
+// A price fetch that ran but could not store every price still fails the command
+fn record_price_fetch(
+    name: &str,
+    result: Result<PriceFetchSummary, TransactionError>,
+    failed: &mut Vec<String>,
+) {
+    match result {
+        Ok(summary) => {
+            println!(
+                "Stored {} {}, {} failed",
+                summary.stored, name, summary.failed
+            );
+            if summary.failed > 0 {
+                failed.push(format!("{} ({} failed)", name, summary.failed));
+            }
+        }
+        Err(e) => {
+            println!("Error fetching {}: {}", name, e);
+            failed.push(name.to_string());
+        }
+    }
+}
+
+pub async fn run_fetch_prices(
+    pg: &PostgreSQL,
+    prices: &PriceProviderChain,
+    coingecko: Option<&SharedCoinGecko>,
+    config: &PricesConfig,
+    args: &FetchPricesArgs,
+) -> Result<(), CliError> {
+    let backfill_start = config.backfill_start();
+    let (from, to) = args.range(backfill_start, last_closed_date())?;
+
+    let mut failed = Vec::new();
+    record_price_fetch(
+        "btc closing prices",
+        backfill_btc_closing_prices(pg, prices, from, to).await,
+        &mut failed,
+    );
+    record_price_fetch(
+        "asset closing prices",
+        fetch_asset_closing_prices(pg, prices, backfill_start).await,
+        &mut failed,
+    );
+    if args.candles || config.candles_enabled {
+        record_price_fetch(
+            "price candles",
+            fetch_price_candles(pg, coingecko).await,
+            &mut failed,
+        );
+    }
+    check_failures("fetch-prices", failed)
+}
@@ -0,0 +1,545 @@
+use chrono::{NaiveDate, Utc};
+use dotenv::dotenv;
+use serde::Deserialize;
+use std::{collections::HashSet, env, fs, path::Path, str::FromStr, time::Duration};
+use thiserror::Error;
+
+use crate::SwapType;
+
+const DEFAULT_SOURCES_PATH: &str = "sources.toml";
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+pub const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0:3000";
+pub const PRICE_PROVIDERS: [&str; 3] = ["coingecko", "midgard", "chainflip"];
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Error reading config file {0}: {1}")]
+    Read(String, std::io::Error),
+    #[error("Error parsing config file {0}: {1}")]
+    Parse(String, toml::de::Error),
+    #[error("Invalid config: {0}")]
+    Invalid(String),
+    #[error("Invalid value for {0}: {1:?}")]
+    Env(&'static str, String),
+}
+
+// Rate limiting and retries for Midgard requests
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MidgardConfig {
+    pub rate_limit_delay_ms: u64,
+    pub max_attempts: u32,
+    pub timeout_secs: u64,
+    // Timeout for the single live request made by /swaps/{tx_id}
+    pub lookup_timeout_secs: u64,
+}
+
+impl Default for MidgardConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit_delay_ms: 5000,
+            max_attempts: 10,
+            timeout_secs: 15,
+            lookup_timeout_secs: 5,
+        }
+    }
+}
+
+impl MidgardConfig {
+    pub fn rate_limit_delay(&self) -> Duration {
+        Duration::from_millis(self.rate_limit_delay_ms)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn lookup_timeout(&self) -> Duration {
+        Duration::from_secs(self.lookup_timeout_secs)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ChainflipConfig {
+    pub base_url: String,
+    pub page_size: i32,
+    pub max_attempts: u32,
+    pub timeout_secs: u64,
+    pub poll_interval_secs: u64,
+}
+
+impl Default for ChainflipConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://reporting-service.chainflip.io/graphql".to_string(),
+            page_size: 30,
+            max_attempts: 10,
+            timeout_secs: 15,
+            poll_interval_secs: 900,
+        }
+    }
+}
+
+impl ChainflipConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+// CoinGecko is optional; without an API key pricing falls back to the other providers
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CoinGeckoConfig {
+    pub base_url: String,
+    pub api_key: Option<String>,
+    // Pause between requests; the default keeps under the demo plan's 30 calls per minute
+    pub request_delay_ms: u64,
+}
+
+impl Default for CoinGeckoConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://api.coingecko.com/api/v3".to_string(),
+            api_key: None,
+            request_delay_ms: 2500,
+        }
+    }
+}
+
+impl CoinGeckoConfig {
+    pub fn request_delay(&self) -> Duration {
+        Duration::from_millis(self.request_delay_ms)
+    }
+}
+
+// When a pending swap stops being retried and moves to dead_letter_swaps
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PendingConfig {
+    pub max_attempts: i32,
+    pub max_age_hours: i64,
+}
+
+impl Default for PendingConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 500,
+            max_age_hours: 48,
+        }
+    }
+}
+
+// Price providers in the order they are tried, and what the price jobs cover
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PricesConfig {
+    pub providers: Vec<String>,
+    // First day of the closing price backfill; a year ago when unset
+    pub backfill_start: Option<NaiveDate>,
+    pub candles_enabled: bool,
+}
+
+impl Default for PricesConfig {
+    fn default() -> Self {
+        Self {
+            providers: PRICE_PROVIDERS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            backfill_start: None,
+            candles_enabled: false,
+        }
+    }
+}
+
+impl PricesConfig {
+    // CoinGecko's demo plan only serves a year of history
+    pub fn backfill_start(&self) -> NaiveDate {
+        self.backfill_start
+            .unwrap_or_else(|| Utc::now().date_naive() - chrono::Duration::days(365))
+    }
+}
+
+// Admin routes are disabled unless a token is configured
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AdminConfig {
+    pub token: Option<String>,
+}
+
+impl AdminConfig {
+    // An empty token leaves the routes disabled
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref().filter(|token| !token.is_empty())
+    }
+}
+
+// How often the hourly background jobs run
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct JobsConfig {
+    pub valuation_interval_secs: u64,
+    pub volume_rollup_interval_secs: u64,
+    // Trailing days the rollup job recomputes each run; must cover pending.max_age_hours
+    pub volume_rollup_window_days: i64,
+    pub price_candles_interval_secs: u64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            valuation_interval_secs: 3600,
+            volume_rollup_interval_secs: 3600,
+            volume_rollup_window_days: 3,
+            price_candles_interval_secs: 3600,
+        }
+    }
+}
+
+// Service settings from config.toml (or CONFIG_PATH), overridden by environment variables.
+// Sources are configured separately in sources.toml.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    pub database_url: String,
+    pub bind_address: String,
+    // Apply pending migrations on startup; otherwise run the migrate subcommand
+    pub auto_migrate: bool,
+    pub midgard: MidgardConfig,
+    pub chainflip: ChainflipConfig,
+    pub coingecko: CoinGeckoConfig,
+    pub jobs: JobsConfig,
+    pub pending: PendingConfig,
+    pub prices: PricesConfig,
+    pub admin: AdminConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database_url: String::new(),
+            bind_address: DEFAULT_BIND_ADDRESS.to_string(),
+            auto_migrate: true,
+            midgard: MidgardConfig::default(),
+            chainflip: ChainflipConfig::default(),
+            coingecko: CoinGeckoConfig::default(),
+            jobs: JobsConfig::default(),
+            pending: PendingConfig::default(),
+            prices: PricesConfig::default(),
+            admin: AdminConfig::default(),
+        }
+    }
+}
+
+fn env_override<T: FromStr>(name: &'static str, target: &mut T) -> Result<(), ConfigError> {
+    if let Ok(value) = env::var(name) {
+        *target = value
+            .parse()
+            .map_err(|_| ConfigError::Env(name, value.clone()))?;
+    }
+    Ok(())
+}
+
+fn env_override_some<T: FromStr>(
+    name: &'static str,
+    target: &mut Option<T>,
+) -> Result<(), ConfigError> {
+    if let Ok(value) = env::var(name) {
+        *target = Some(
+            value
+                .parse()
+                .map_err(|_| ConfigError::Env(name, value.clone()))?,
+        );
+    }
+    Ok(())
+}
+
+impl Config {
+    pub fn parse(contents: &str, path: &str) -> Result<Self, ConfigError> {
+        toml::from_str(contents).map_err(|e| ConfigError::Parse(path.to_string(), e))
+    }
+
+    // The config file is optional; every setting has a default except database_url
+    pub fn load() -> Result<Self, ConfigError> {
+        dotenv().ok();
+        let path = env::var("CONFIG_PATH").unwrap_or(DEFAULT_CONFIG_PATH.to_string());
+        let mut config = if Path::new(&path).exists() {
+            let contents =
+                fs::read_to_string(&path).map_err(|e| ConfigError::Read(path.clone(), e))?;
+            Self::parse(&contents, &path)?
+        } else {
+            Self::default()
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        env_override("DATABASE_URL", &mut self.database_url)?;
+        env_override("BIND_ADDRESS", &mut self.bind_address)?;
+        env_override("AUTO_MIGRATE", &mut self.auto_migrate)?;
+        env_override(
+            "MIDGARD_RATE_LIMIT_DELAY_MS",
+            &mut self.midgard.rate_limit_delay_ms,
+        )?;
+        env_override("MIDGARD_MAX_ATTEMPTS", &mut self.midgard.max_attempts)?;
+        env_override("MIDGARD_TIMEOUT_SECS", &mut self.midgard.timeout_secs)?;
+        env_override(
+            "MIDGARD_LOOKUP_TIMEOUT_SECS",
+            &mut self.midgard.lookup_timeout_secs,
+        )?;
+        env_override("CHAINFLIP_BASE_URL", &mut self.chainflip.base_url)?;
+        env_override("CHAINFLIP_PAGE_SIZE", &mut self.chainflip.page_size)?;
+        env_override("CHAINFLIP_MAX_ATTEMPTS", &mut self.chainflip.max_attempts)?;
+        env_override("CHAINFLIP_TIMEOUT_SECS", &mut self.chainflip.timeout_secs)?;
+        env_override(
+            "CHAINFLIP_POLL_INTERVAL_SECS",
+            &mut self.chainflip.poll_interval_secs,
+        )?;
+        env_override("COINGECKO_BASE_URL", &mut self.coingecko.base_url)?;
+        if let Ok(api_key) = env::var("COINGECKO_API_KEY") {
+            self.coingecko.api_key = Some(api_key);
+        }
+        env_override(
+            "COINGECKO_REQUEST_DELAY_MS",
+            &mut self.coingecko.request_delay_ms,
+        )?;
+        env_override(
+            "VALUATION_INTERVAL_SECS",
+            &mut self.jobs.valuation_interval_secs,
+        )?;
+        env_override(
+            "VOLUME_ROLLUP_INTERVAL_SECS",
+            &mut self.jobs.volume_rollup_interval_secs,
+        )?;
+        env_override(
+            "VOLUME_ROLLUP_WINDOW_DAYS",
+            &mut self.jobs.volume_rollup_window_days,
+        )?;
+        env_override(
+            "PRICE_CANDLES_INTERVAL_SECS",
+            &mut self.jobs.price_candles_interval_secs,
+        )?;
+        env_override("PENDING_MAX_ATTEMPTS", &mut self.pending.max_attempts)?;
+        env_override("PENDING_MAX_AGE_HOURS", &mut self.pending.max_age_hours)?;
+        if let Ok(providers) = env::var("PRICE_PROVIDERS") {
+            self.prices.providers = providers
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        env_override_some(
+            "CLOSING_PRICE_BACKFILL_START",
+            &mut self.prices.backfill_start,
+        )?;
+        env_override("PRICE_CANDLES_ENABLED", &mut self.prices.candles_enabled)?;
+        if let Ok(token) = env::var("ADMIN_TOKEN") {
+            self.admin.token = Some(token);
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.database_url.trim().is_empty() {
+            return Err(ConfigError::Invalid(
+                "database_url is required; set DATABASE_URL or database_url in the config file"
+                    .to_string(),
+            ));
+        }
+        let valid_bind = match self.bind_address.rsplit_once(':') {
+            Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok(),
+            None => false,
+        };
+        if !valid_bind {
+            return Err(ConfigError::Invalid(format!(
+                "bind_address must be host:port, got {:?}",
+                self.bind_address
+            )));
+        }
+        for (name, url) in [
+            ("chainflip.base_url", &self.chainflip.base_url),
+            ("coingecko.base_url", &self.coingecko.base_url),
+        ] {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(ConfigError::Invalid(format!(
+                    "{} must be an http(s) URL, got {:?}",
+                    name, url
+                )));
+            }
+        }
+        if !(1..=100).contains(&self.chainflip.page_size) {
+            return Err(ConfigError::Invalid(format!(
+                "chainflip.page_size must be between 1 and 100, got {}",
+                self.chainflip.page_size
+            )));
+        }
+        for (name, value) in [
+            ("midgard.max_attempts", self.midgard.max_attempts as u64),
+            ("midgard.timeout_secs", self.midgard.timeout_secs),
+            (
+                "midgard.lookup_timeout_secs",
+                self.midgard.lookup_timeout_secs,
+            ),
+            ("chainflip.max_attempts", self.chainflip.max_attempts as u64),
+            ("chainflip.timeout_secs", self.chainflip.timeout_secs),
+            (
+                "chainflip.poll_interval_secs",
+                self.chainflip.poll_interval_secs,
+            ),
+            (
+                "jobs.valuation_interval_secs",
+                self.jobs.valuation_interval_secs,
+            ),
+            (
+                "jobs.volume_rollup_interval_secs",
+                self.jobs.volume_rollup_interval_secs,
+            ),
+            (
+                "jobs.price_candles_interval_secs",
+                self.jobs.price_candles_interval_secs,
+            ),
+        ] {
+            if value == 0 {
+                return Err(ConfigError::Invalid(format!(
+                    "{} must be greater than zero",
+                    name
+                )));
+            }
+        }
+        if self.pending.max_attempts <= 0 || self.pending.max_age_hours <= 0 {
+            return Err(ConfigError::Invalid(
+                "pending.max_attempts and pending.max_age_hours must be greater than zero"
+                    .to_string(),
+            ));
+        }
+        // Pending swaps keep their original date when they settle, so the window must reach back
+        // as far as a swap can stay pending
+        if self.jobs.volume_rollup_window_days.saturating_mul(24) < self.pending.max_age_hours {
+            return Err(ConfigError::Invalid(format!(
+                "jobs.volume_rollup_window_days must cover pending.max_age_hours ({}h)",
+                self.pending.max_age_hours
+            )));
+        }
+        if self.prices.providers.is_empty() {
+            return Err(ConfigError::Invalid(
+                "prices.providers must name at least one provider".to_string(),
+            ));
+        }
+        let mut providers = HashSet::new();
+        for provider in &self.prices.providers {
+            if !PRICE_PROVIDERS.contains(&provider.as_str()) {
+                return Err(ConfigError::Invalid(format!(
+                    "unknown price provider {:?}, expected one of {}",
+                    provider,
+                    PRICE_PROVIDERS.join(", ")
+                )));
+            }
+            if !providers.insert(provider.as_str()) {
+                return Err(ConfigError::Invalid(format!(
+                    "duplicate price provider {:?}",
+                    provider
+                )));
+            }
+        }
+        if self.prices.backfill_start() > Utc::now().date_naive() {
+            return Err(ConfigError::Invalid(format!(
+                "prices.backfill_start must not be in the future, got {}",
+                self.prices.backfill_start()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn default_retry_interval_secs() -> u64 {
+    300
+}
+
+// A single Midgard action feed, e.g. native BTC swaps written to `native_swaps_thorchain`
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceConfig {
+    pub name: String,
+    pub endpoint: String,
+    pub asset: String,
+    pub swap_type: SwapType,
+    pub table: String,
+    pub poll_interval_secs: u64,
+    #[serde(default = "default_retry_interval_secs")]
+    pub retry_interval_secs: u64,
+    #[serde(default)]
+    pub reconcile_daily: bool,
+}
+
+impl SourceConfig {
+    pub fn base_url(&self) -> String {
+        format!("{}?asset={}&type=swap", self.endpoint, self.asset)
+    }
+
+    pub fn label(&self) -> String {
+        let swap_type_str = match self.swap_type {
+            SwapType::NATIVE => "Native Swaps",
+            SwapType::TRADE => "Trade Swaps",
+        };
+        format!("{} ({})", swap_type_str, self.name)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SourcesConfig {
+    pub sources: Vec<SourceConfig>,
+}
+
+impl SourcesConfig {
+    pub fn parse(contents: &str, path: &str) -> Result<Self, ConfigError> {
+        let config: SourcesConfig =
+            toml::from_str(contents).map_err(|e| ConfigError::Parse(path.to_string(), e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn load() -> Result<Self, ConfigError> {
+        dotenv().ok();
+        let path = env::var("SOURCES_CONFIG").unwrap_or(DEFAULT_SOURCES_PATH.to_string());
+        let contents = fs::read_to_string(&path).map_err(|e| ConfigError::Read(path.clone(), e))?;
+        Self::parse(&contents, &path)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut names = HashSet::new();
+        for source in &self.sources {
+            if source.name.is_empty() {
+                return Err(ConfigError::Invalid(
+                    "source name cannot be empty".to_string(),
+                ));
+            }
+            if !names.insert(source.name.as_str()) {
+                return Err(ConfigError::Invalid(format!(
+                    "duplicate source name: {}",
+                    source.name
+                )));
+            }
+            if source.table.is_empty()
+                || !source
+                    .table
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(ConfigError::Invalid(format!(
+                    "invalid table name for source {}: {:?}",
+                    source.name, source.table
+                )));
+            }
+            if source.poll_interval_secs == 0 || source.retry_interval_secs == 0 {
+                return Err(ConfigError::Invalid(format!(
+                    "intervals must be greater than zero for source {}",
+                    source.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,68 +1,146 @@
-use dotenv::dotenv;
-use sqlx::{postgres::PgPool, Error as SqlxError};
-use std::env;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{
+    migrate::{MigrateError, Migrator},
+    postgres::PgPool,
+    Error as SqlxError, Executor, Postgres, QueryBuilder, Transaction,
+};
 
 use crate::{
     models::{
         actions_model::SwapTransactionFromatted,
+        addresses::{AddressActivity, AssetVolume},
         chainflip_swaps::{ChainflipSwap, ChainflipSwapDetailed},
-        closing_prices::ClosingPriceInterval,
+        closing_prices::{AssetClosingPrice, ClosingPriceInterval, PriceCandle},
+        pending_swaps::{DeadLetterSwap, PendingSwap},
+        sync_cursors::{SyncCursor, SyncDirection},
+        unified_swaps::{Protocol, UnifiedSwap},
+        volume_stats::VolumeStat,
+    },
+    routes::{
+        chainflip_swaps::ChainflipSwapsQuery,
+        stats::VolumeStatsQuery,
+        swap_history::{OrderType, SwapHistoryQuery},
     },
-    routes::swap_history::OrderType,
     utils::{format_date_for_sql, sanitize_string},
 };
 
+// Asset names BTC goes by across the THORChain native/trade pools and Chainflip
+const BTC_ASSETS: &str = "('BTC.BTC', 'BTC~BTC', 'BTC')";
+
+// Swap table served by /swaps; loaded outside the sync but valued like the source tables
+pub const SWAP_HISTORY_TABLE: &str = "btc_user_data";
+
+// Columns the sync writes and the valuation job fills in every THORChain source table
+pub const SWAP_TABLE_COLUMNS: [&str; 27] = [
+    "timestamp",
+    "date",
+    "time",
+    "tx_id",
+    "in_asset",
+    "in_amount",
+    "in_address",
+    "out_asset_1",
+    "out_amount_1",
+    "out_address_1",
+    "out_asset_2",
+    "out_amount_2",
+    "out_address_2",
+    "network_fees",
+    "liquidity_fee",
+    "swap_slip_bps",
+    "affiliate_fee_bps",
+    "affiliate_address",
+    "memo",
+    "is_streaming_swap",
+    "streaming_quantity",
+    "streaming_interval",
+    "in_amount_usd",
+    "out_amount_usd",
+    "btc_price_usd",
+    "btc_value_usd",
+    "btc_price_source",
+];
+
+// Rows per INSERT in a batched insert; at 24 parameters a row this stays well under
+// Postgres' limit of 65535 bind parameters per statement
+pub(crate) const INSERT_CHUNK_SIZE: usize = 1000;
+
 #[derive(Clone)]
 pub struct PostgreSQL {
     pub pool: PgPool,
 }
 
+// Outcome of a batched swap insert. Conflicts are rows skipped by ON CONFLICT (tx_id)
+// because the swap was already stored, or appeared twice in the batch.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InsertSummary {
+    pub inserted: u64,
+    pub conflicts: u64,
+}
+
+impl InsertSummary {
+    pub fn new(rows: u64, inserted: u64) -> Self {
+        Self {
+            inserted,
+            conflicts: rows.saturating_sub(inserted),
+        }
+    }
+
+    pub fn add(&mut self, other: InsertSummary) {
+        self.inserted += other.inserted;
+        self.conflicts += other.conflicts;
+    }
+}
+
+// Swaps valued by one apply_btc_valuation run and the earliest day they fall on, so the
+// volume rollups for those days can be refreshed
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ValuationSummary {
+    pub valued: u64,
+    pub earliest_date: Option<NaiveDate>,
+}
+
+// Versioned schema from migrations/, embedded at compile time
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 impl PostgreSQL {
-    pub async fn init() -> Result<Self, SqlxError> {
-        dotenv().ok();
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let pool = PgPool::connect(&database_url).await?;
+    pub async fn init(database_url: &str) -> Result<Self, SqlxError> {
+        let pool = PgPool::connect(database_url).await?;
         println!("Connected to PostgreSQL");
         Ok(PostgreSQL { pool })
     }
 
-    pub async fn insert_new_record(
-        &self,
-        record: SwapTransactionFromatted,
-        table_name: &str,
-    ) -> Result<(), SqlxError> {
-        let query = format!(
-            r#"
-            INSERT INTO {} (
-                timestamp, date, time, tx_id, 
-                in_asset, in_amount, in_address, 
-                out_asset_1, out_amount_1, out_address_1, 
-                out_asset_2, out_amount_2, out_address_2
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-            ON CONFLICT (tx_id) DO NOTHING"#,
-            table_name
-        );
+    // Applies migrations not yet recorded in _sqlx_migrations. Every migration is idempotent,
+    // so databases set up before versioning can run the full history.
+    pub async fn migrate(&self) -> Result<(), MigrateError> {
+        MIGRATOR.run(&self.pool).await?;
+        println!("Database Migrations Applied");
+        Ok(())
+    }
 
-        let date = format_date_for_sql(&record.date).unwrap_or_default();
+    // Migrations only cover the built-in tables, so a source added to sources.toml needs its
+    // table created by hand. Returns the swap columns `table_name` lacks; all of them when the
+    // table doesn't exist.
+    pub async fn missing_swap_columns(&self, table_name: &str) -> Result<Vec<String>, SqlxError> {
+        let query = r#"
+            SELECT required.name
+            FROM UNNEST(CAST($1 AS TEXT[])) WITH ORDINALITY AS required(name, position)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM information_schema.columns AS columns
+                WHERE columns.table_schema = CURRENT_SCHEMA()
+                    AND columns.table_name = $2
+                    AND columns.column_name = required.name
+            )
+            ORDER BY required.position
+        "#;
 
-        sqlx::query(query.as_str().as_ref())
-            .bind(record.timestamp)
-            .bind(date)
-            .bind(record.time)
-            .bind(record.tx_id)
-            .bind(record.in_asset)
-            .bind(record.in_amount)
-            .bind(record.in_address)
-            .bind(record.out_asset_1)
-            .bind(record.out_amount_1)
-            .bind(record.out_address_1)
-            .bind(record.out_asset_2)
-            .bind(record.out_amount_2)
-            .bind(record.out_address_2)
-            .execute(&self.pool)
+        let missing = sqlx::query_as::<_, (String,)>(query)
+            .bind(&SWAP_TABLE_COLUMNS[..])
+            .bind(table_name)
+            .fetch_all(&self.pool)
             .await?;
 
-        Ok(())
+        Ok(missing.into_iter().map(|(name,)| name).collect())
     }
 
     pub async fn insert_closing_price(
@@ -72,78 +150,336 @@ impl PostgreSQL {
         let query = r#"
             INSERT INTO btc_closing_prices (
                 date,
-                closing_price_usd
+                closing_price_usd,
+                price_at,
+                provider
             )
-            VALUES ($1, $2)
+            VALUES ($1, $2, CAST(CAST($1 AS DATE) + 1 AS TIMESTAMP) AT TIME ZONE 'UTC', $3)
             ON CONFLICT (date) DO UPDATE 
-            SET closing_price_usd = EXCLUDED.closing_price_usd
+            SET
+                closing_price_usd = EXCLUDED.closing_price_usd,
+                price_at = EXCLUDED.price_at,
+                provider = EXCLUDED.provider
         "#;
 
         sqlx::query(query)
             .bind(record.date)
             .bind(record.closing_price_usd)
+            .bind(record.provider)
             .execute(&self.pool)
             .await?;
 
         Ok(())
     }
-    pub async fn insert_bulk(
-        &self,
+    // Multi-row INSERT for `records`; existing tx_ids are skipped
+    pub(crate) fn insert_swaps_query(
         table_name: &str,
         records: Vec<SwapTransactionFromatted>,
-    ) -> Result<(), SqlxError> {
-        if records.is_empty() {
-            return Ok(());
-        }
-
-        let query = format!(
+    ) -> QueryBuilder<'static, Postgres> {
+        let mut builder = QueryBuilder::new(format!(
             "INSERT INTO {} (
                 timestamp, date, time, tx_id, 
                 in_asset, in_amount, in_address, 
                 out_asset_1, out_amount_1, out_address_1, 
-                out_asset_2, out_amount_2, out_address_2
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-            ON CONFLICT (tx_id) DO NOTHING",
+                out_asset_2, out_amount_2, out_address_2,
+                network_fees, liquidity_fee, swap_slip_bps,
+                affiliate_fee_bps, affiliate_address, memo,
+                is_streaming_swap, streaming_quantity, streaming_interval,
+                in_amount_usd, out_amount_usd
+            ) ",
             table_name
-        );
-
-        let mut successful_count = 0;
-        let total_records = records.len();
+        ));
 
-        for record in records {
+        builder.push_values(records, |mut row, record| {
             let date = format_date_for_sql(&record.date).unwrap_or_default();
+            row.push_bind(record.timestamp)
+                .push_bind(date)
+                .push_bind(record.time)
+                .push_bind(record.tx_id)
+                .push_bind(sanitize_string(&record.in_asset))
+                .push_bind(record.in_amount)
+                .push_bind(sanitize_string(&record.in_address))
+                .push_bind(sanitize_string(&record.out_asset_1))
+                .push_bind(record.out_amount_1)
+                .push_bind(sanitize_string(&record.out_address_1))
+                .push_bind(record.out_asset_2.as_deref().map(sanitize_string))
+                .push_bind(record.out_amount_2)
+                .push_bind(record.out_address_2.as_deref().map(sanitize_string))
+                .push("CAST(")
+                .push_bind_unseparated(record.network_fees)
+                .push_unseparated(" AS JSONB)")
+                .push_bind(record.liquidity_fee)
+                .push_bind(record.swap_slip_bps)
+                .push_bind(record.affiliate_fee_bps)
+                .push_bind(record.affiliate_address)
+                .push_bind(record.memo)
+                .push_bind(record.is_streaming_swap)
+                .push_bind(record.streaming_quantity)
+                .push_bind(record.streaming_interval)
+                .push_bind(record.in_amount_usd)
+                .push_bind(record.out_amount_usd);
+        });
+        builder.push(" ON CONFLICT (tx_id) DO NOTHING");
+        builder
+    }
 
-            match sqlx::query(&query)
-                .bind(record.timestamp)
-                .bind(date)
-                .bind(record.time)
-                .bind(record.tx_id)
-                .bind(sanitize_string(&record.in_asset))
-                .bind(record.in_amount)
-                .bind(sanitize_string(&record.in_address))
-                .bind(sanitize_string(&record.out_asset_1))
-                .bind(record.out_amount_1)
-                .bind(sanitize_string(&record.out_address_1))
-                .bind(record.out_asset_2.as_deref().map(sanitize_string))
-                .bind(record.out_amount_2)
-                .bind(record.out_address_2.as_deref().map(sanitize_string))
-                .execute(&self.pool)
-                .await
-            {
-                Ok(_) => successful_count += 1,
-                Err(e) => {
-                    println!("Error inserting record: {:?}", e);
-                    println!("Failed record");
-                    // Optionally, you could log the specific record that failed
-                }
-            }
+    // Splits `records` into INSERT_CHUNK_SIZE batches, keeping their order
+    pub(crate) fn insert_chunks<T>(mut records: Vec<T>) -> Vec<Vec<T>> {
+        let mut chunks = Vec::new();
+        while !records.is_empty() {
+            let rest = records.split_off(records.len().min(INSERT_CHUNK_SIZE));
+            chunks.push(records);
+            records = rest;
+        }
+        chunks
+    }
+
+    async fn insert_swaps(
+        transaction: &mut Transaction<'_, Postgres>,
+        table_name: &str,
+        records: Vec<SwapTransactionFromatted>,
+    ) -> Result<InsertSummary, SqlxError> {
+        let mut summary = InsertSummary::default();
+        for chunk in Self::insert_chunks(records) {
+            let rows = chunk.len() as u64;
+            let inserted = Self::insert_swaps_query(table_name, chunk)
+                .build()
+                .execute(&mut **transaction)
+                .await?
+                .rows_affected();
+            summary.add(InsertSummary::new(rows, inserted));
         }
+        Ok(summary)
+    }
+
+    // Inserts every record in one transaction, so a failed batch leaves nothing behind
+    pub async fn insert_bulk(
+        &self,
+        table_name: &str,
+        records: Vec<SwapTransactionFromatted>,
+    ) -> Result<InsertSummary, SqlxError> {
+        if records.is_empty() {
+            return Ok(InsertSummary::default());
+        }
+
+        let mut transaction = self.pool.begin().await?;
+        let summary = Self::insert_swaps(&mut transaction, table_name, records).await?;
+        transaction.commit().await?;
+
+        println!(
+            "Inserted {} records, {} already stored",
+            summary.inserted, summary.conflicts
+        );
+        Ok(summary)
+    }
+
+    // Inserts the batch and moves the source's sync cursor in one transaction, so a resumed
+    // sync starts exactly after the last committed batch
+    pub async fn insert_bulk_with_cursor(
+        &self,
+        table_name: &str,
+        records: Vec<SwapTransactionFromatted>,
+        source: &str,
+        direction: SyncDirection,
+        token: &str,
+        timestamp: Option<i64>,
+    ) -> Result<InsertSummary, SqlxError> {
+        let mut transaction = self.pool.begin().await?;
+
+        let summary = Self::insert_swaps(&mut transaction, table_name, records).await?;
+        Self::upsert_sync_cursor(&mut *transaction, source, direction, Some(token), timestamp)
+            .await?;
+
+        transaction.commit().await?;
+        Ok(summary)
+    }
+
+    async fn upsert_sync_cursor<'e, E>(
+        executor: E,
+        source: &str,
+        direction: SyncDirection,
+        token: Option<&str>,
+        timestamp: Option<i64>,
+    ) -> Result<(), SqlxError>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let query = r#"
+            INSERT INTO sync_cursors (source, direction, token, timestamp, updated_at)
+            VALUES ($1, $2, $3, $4, NOW())
+            ON CONFLICT (source, direction) DO UPDATE
+            SET
+                token = EXCLUDED.token,
+                timestamp = COALESCE(EXCLUDED.timestamp, sync_cursors.timestamp),
+                updated_at = EXCLUDED.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(source)
+            .bind(direction.as_str())
+            .bind(token)
+            .bind(timestamp)
+            .execute(executor)
+            .await?;
 
-        println!("Inserted {}/{} records", successful_count, total_records);
+        Ok(())
+    }
+
+    pub async fn fetch_sync_cursor(
+        &self,
+        source: &str,
+        direction: SyncDirection,
+    ) -> Result<Option<SyncCursor>, SqlxError> {
+        let query = r#"
+            SELECT source, direction, token, timestamp, updated_at
+            FROM sync_cursors
+            WHERE source = $1 AND direction = $2
+        "#;
+
+        sqlx::query_as::<_, SyncCursor>(query)
+            .bind(source)
+            .bind(direction.as_str())
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    pub async fn insert_pending_swap(
+        &self,
+        tx_id: &str,
+        source: &str,
+        swap_type: &str,
+    ) -> Result<(), SqlxError> {
+        let query = r#"
+            INSERT INTO pending_swaps (tx_id, source, swap_type)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (tx_id, source) DO NOTHING
+        "#;
+
+        sqlx::query(query)
+            .bind(tx_id)
+            .bind(source)
+            .bind(swap_type)
+            .execute(&self.pool)
+            .await?;
 
         Ok(())
     }
 
+    pub async fn fetch_pending_swaps(&self, source: &str) -> Result<Vec<PendingSwap>, SqlxError> {
+        let query = r#"
+            SELECT
+                tx_id, source, swap_type, first_seen_at, last_attempt_at,
+                attempt_count, last_error
+            FROM pending_swaps
+            WHERE source = $1
+            ORDER BY first_seen_at ASC
+        "#;
+
+        let records = sqlx::query_as::<_, PendingSwap>(query)
+            .bind(source)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    pub async fn record_pending_swap_attempt(
+        &self,
+        tx_id: &str,
+        source: &str,
+        error: Option<&str>,
+    ) -> Result<(), SqlxError> {
+        let query = r#"
+            UPDATE pending_swaps
+            SET attempt_count = attempt_count + 1,
+                last_attempt_at = CURRENT_TIMESTAMP,
+                last_error = $3
+            WHERE tx_id = $1 AND source = $2
+        "#;
+
+        sqlx::query(query)
+            .bind(tx_id)
+            .bind(source)
+            .bind(error)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_pending_swap(&self, tx_id: &str, source: &str) -> Result<(), SqlxError> {
+        sqlx::query("DELETE FROM pending_swaps WHERE tx_id = $1 AND source = $2")
+            .bind(tx_id)
+            .bind(source)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn move_pending_swap_to_dead_letter(
+        &self,
+        tx_id: &str,
+        source: &str,
+        reason: &str,
+    ) -> Result<(), SqlxError> {
+        let insert_query = r#"
+            INSERT INTO dead_letter_swaps (
+                tx_id, source, swap_type, first_seen_at, last_attempt_at,
+                attempt_count, last_error, reason
+            )
+            SELECT
+                tx_id, source, swap_type, first_seen_at, last_attempt_at,
+                attempt_count, last_error, $3
+            FROM pending_swaps
+            WHERE tx_id = $1 AND source = $2
+            ON CONFLICT (tx_id, source) DO UPDATE
+            SET
+                last_attempt_at = EXCLUDED.last_attempt_at,
+                attempt_count = EXCLUDED.attempt_count,
+                last_error = EXCLUDED.last_error,
+                reason = EXCLUDED.reason,
+                dead_lettered_at = CURRENT_TIMESTAMP
+        "#;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(insert_query)
+            .bind(tx_id)
+            .bind(source)
+            .bind(reason)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM pending_swaps WHERE tx_id = $1 AND source = $2")
+            .bind(tx_id)
+            .bind(source)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn fetch_dead_letter_swaps(
+        &self,
+        source: Option<String>,
+    ) -> Result<Vec<DeadLetterSwap>, SqlxError> {
+        let query = r#"
+            SELECT
+                tx_id, source, swap_type, first_seen_at, last_attempt_at,
+                attempt_count, last_error, reason, dead_lettered_at
+            FROM dead_letter_swaps
+            WHERE ($1::VARCHAR IS NULL OR source = $1)
+            ORDER BY dead_lettered_at DESC
+        "#;
+
+        let records = sqlx::query_as::<_, DeadLetterSwap>(query)
+            .bind(source)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
     pub async fn fetch_latest_timestamp(&self, table_name: &str) -> Result<Option<i32>, SqlxError> {
         let query = format!("SELECT MAX(timestamp) FROM {}", table_name);
         let result: Option<i32> = sqlx::query_scalar(&query)
@@ -165,71 +501,904 @@ impl PostgreSQL {
         Ok(result)
     }
 
+    // Builds the WHERE clause shared by fetch_all and count_all. Parameters are numbered
+    // from $1 in bind order: search, date, then the cursor when `with_cursor` is set.
+    fn swap_history_filters(query: &SwapHistoryQuery, with_cursor: bool) -> (String, usize) {
+        let mut clauses = String::from("WHERE (1 = 1)");
+        let mut param = 1;
+        if query.search.is_some() {
+            clauses.push_str(&format!(
+                " AND (tx_id LIKE ${0} OR in_address LIKE ${0} OR out_address_1 LIKE ${0} OR out_address_2 LIKE ${0})",
+                param
+            ));
+            param += 1;
+        }
+        if query.date.is_some() {
+            // Swap tables store the date as YYYY-MM-DD text
+            clauses.push_str(&format!(
+                " AND date = TO_CHAR(CAST(${} AS DATE), 'YYYY-MM-DD')",
+                param
+            ));
+            param += 1;
+        }
+        if with_cursor && query.cursor.is_some() {
+            let comparison = match query.order {
+                OrderType::ASC => ">",
+                OrderType::DESC => "<",
+            };
+            clauses.push_str(&format!(
+                " AND (timestamp, tx_id) {} (${}, ${})",
+                comparison,
+                param,
+                param + 1
+            ));
+            param += 2;
+        }
+        (clauses, param)
+    }
+
+    // Fetches one row more than the page size so the caller can tell whether a next page exists
     pub async fn fetch_all(
         &self,
         table_name: &str,
-        order: OrderType,
-        limit: u64,
-        sort_by: String,
-        offset: u64,
-        search: Option<String>,
-        date: Option<String>,
+        query: &SwapHistoryQuery,
     ) -> Result<Vec<SwapTransactionFromatted>, SqlxError> {
+        let (filters, next_param) = Self::swap_history_filters(query, true);
+        let order = match query.order {
+            OrderType::ASC => "ASC",
+            OrderType::DESC => "DESC",
+        };
+        let pagination = if query.cursor.is_some() {
+            format!("LIMIT ${}", next_param)
+        } else {
+            format!("LIMIT ${} OFFSET ${}", next_param, next_param + 1)
+        };
         let base_query = format!(
             r#"
             SELECT 
                 timestamp, date, time, tx_id, 
                 in_asset, in_amount, in_address,
                 out_asset_1, out_amount_1, out_address_1,
-                out_asset_2, out_amount_2, out_address_2
+                out_asset_2, out_amount_2, out_address_2,
+                in_amount_usd, out_amount_usd, btc_price_usd, btc_value_usd
             FROM {}
-            WHERE (1 = 1)
             {}
+            ORDER BY {} {}, tx_id {}
             {}
-            ORDER BY {} {}
-            LIMIT $1 OFFSET $2
             "#,
             table_name,
-            if search.is_some() {
-                "AND (tx_id LIKE $3 OR in_address LIKE $4 OR out_address_1 LIKE $5 OR out_address_2 LIKE $6)"
-            } else {
-                ""
-            },
-            if let Some(_) = date {
-                if search.is_some() {
-                    "AND date = $7"
-                } else {
-                    "AND date = $3"
-                }
-            } else {
-                ""
-            },
-            sort_by,
-            match order {
-                OrderType::ASC => "ASC",
-                OrderType::DESC => "DESC",
-            },
+            filters,
+            query.sort_by.column(),
+            order,
+            order,
+            pagination,
         );
 
-        let mut query = sqlx::query_as::<_, SwapTransactionFromatted>(&base_query);
-        query = query.bind(limit as i64).bind(offset as i64);
+        let mut sql_query = sqlx::query_as::<_, SwapTransactionFromatted>(&base_query);
+        if let Some(search_term) = &query.search {
+            sql_query = sql_query.bind(format!("%{}%", search_term));
+        }
+        if let Some(date_value) = query.date {
+            sql_query = sql_query.bind(date_value);
+        }
+        match &query.cursor {
+            Some(cursor) => {
+                sql_query = sql_query
+                    .bind(cursor.timestamp)
+                    .bind(cursor.tx_id.clone())
+                    .bind(query.limit as i64 + 1);
+            }
+            None => {
+                sql_query = sql_query.bind(query.limit as i64 + 1).bind(query.offset());
+            }
+        }
+
+        let records = sql_query.fetch_all(&self.pool).await?;
+        Ok(records)
+    }
 
-        if let Some(search_term) = search {
-            let search_pattern = format!("%{}%", search_term);
-            query = query
-                .bind(search_pattern.clone())
-                .bind(search_pattern.clone())
-                .bind(search_pattern.clone())
-                .bind(search_pattern.clone());
+    pub async fn count_all(
+        &self,
+        table_name: &str,
+        query: &SwapHistoryQuery,
+    ) -> Result<i64, SqlxError> {
+        let (filters, _) = Self::swap_history_filters(query, false);
+        let count_query = format!("SELECT COUNT(*) FROM {} {}", table_name, filters);
+
+        let mut sql_query = sqlx::query_scalar::<_, i64>(&count_query);
+        if let Some(search_term) = &query.search {
+            sql_query = sql_query.bind(format!("%{}%", search_term));
+        }
+        if let Some(date_value) = query.date {
+            sql_query = sql_query.bind(date_value);
         }
 
-        if let Some(date_value) = date {
-            query = query.bind(date_value);
+        let total = sql_query.fetch_one(&self.pool).await?;
+        Ok(total)
+    }
+
+    // A stored swap still tracked in pending_swaps for its source is reported as pending
+    pub async fn fetch_swap_by_tx_id(
+        &self,
+        table_name: &str,
+        source: &str,
+        tx_id: &str,
+    ) -> Result<Option<SwapTransactionFromatted>, SqlxError> {
+        let query = format!(
+            r#"
+            SELECT
+                CAST(timestamp AS BIGINT) AS timestamp, CAST(date AS TEXT) AS date, time, tx_id,
+                in_asset, in_amount, in_address,
+                out_asset_1, out_amount_1, out_address_1,
+                out_asset_2, out_amount_2, out_address_2,
+                CASE
+                    WHEN EXISTS (
+                        SELECT 1 FROM pending_swaps AS pending
+                        WHERE pending.tx_id = swaps.tx_id AND pending.source = $2
+                    ) THEN 'pending'
+                    ELSE 'success'
+                END AS status,
+                in_amount_usd, out_amount_usd,
+                CAST(network_fees AS TEXT) AS network_fees, liquidity_fee, swap_slip_bps,
+                affiliate_fee_bps, affiliate_address, memo,
+                is_streaming_swap, streaming_quantity, streaming_interval,
+                btc_price_usd, btc_value_usd
+            FROM {} AS swaps
+            WHERE tx_id = $1
+            "#,
+            table_name
+        );
+
+        let record = sqlx::query_as::<_, SwapTransactionFromatted>(&query)
+            .bind(tx_id)
+            .bind(source)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(record)
+    }
+
+    // btc_user_data predates the swap metadata columns and only ever held settled swaps
+    pub async fn fetch_swap_history_by_tx_id(
+        &self,
+        tx_id: &str,
+    ) -> Result<Option<SwapTransactionFromatted>, SqlxError> {
+        let query = format!(
+            r#"
+            SELECT
+                CAST(timestamp AS BIGINT) AS timestamp, CAST(date AS TEXT) AS date, time, tx_id,
+                in_asset, in_amount, in_address,
+                out_asset_1, out_amount_1, out_address_1,
+                out_asset_2, out_amount_2, out_address_2,
+                'success' AS status,
+                in_amount_usd, out_amount_usd, btc_price_usd, btc_value_usd
+            FROM {}
+            WHERE tx_id = $1
+            "#,
+            SWAP_HISTORY_TABLE
+        );
+
+        let record = sqlx::query_as::<_, SwapTransactionFromatted>(&query)
+            .bind(tx_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(record)
+    }
+
+    pub async fn insert_asset_closing_price(
+        &self,
+        record: AssetClosingPrice,
+    ) -> Result<(), SqlxError> {
+        let query = r#"
+            INSERT INTO closing_prices (asset, date, coin_id, closing_price_usd, price_at, provider)
+            VALUES ($1, $2, $3, $4, CAST(CAST($2 AS DATE) + 1 AS TIMESTAMP) AT TIME ZONE 'UTC', $5)
+            ON CONFLICT (asset, date) DO UPDATE
+            SET
+                coin_id = EXCLUDED.coin_id,
+                closing_price_usd = EXCLUDED.closing_price_usd,
+                price_at = EXCLUDED.price_at,
+                provider = EXCLUDED.provider
+        "#;
+
+        sqlx::query(query)
+            .bind(record.asset)
+            .bind(record.date)
+            .bind(record.coin_id)
+            .bind(record.closing_price_usd)
+            .bind(record.provider)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn insert_price_candle(&self, candle: PriceCandle) -> Result<(), SqlxError> {
+        let query = r#"
+            INSERT INTO price_candles (asset, open_time, open, high, low, close, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW())
+            ON CONFLICT (asset, open_time) DO UPDATE
+            SET
+                open = EXCLUDED.open,
+                high = EXCLUDED.high,
+                low = EXCLUDED.low,
+                close = EXCLUDED.close,
+                updated_at = EXCLUDED.updated_at
+        "#;
+
+        sqlx::query(query)
+            .bind(candle.asset)
+            .bind(candle.open_time)
+            .bind(candle.open)
+            .bind(candle.high)
+            .bind(candle.low)
+            .bind(candle.close)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn fetch_latest_candle_time(
+        &self,
+        asset: &str,
+    ) -> Result<Option<DateTime<Utc>>, SqlxError> {
+        let (latest,) = sqlx::query_as::<_, (Option<DateTime<Utc>>,)>(
+            "SELECT MAX(open_time) FROM price_candles WHERE asset = $1",
+        )
+        .bind(asset)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(latest)
+    }
+
+    pub async fn fetch_closing_price_keys(
+        &self,
+        from: NaiveDate,
+    ) -> Result<Vec<(String, NaiveDate)>, SqlxError> {
+        let query = "SELECT asset, date FROM closing_prices WHERE date >= $1";
+
+        sqlx::query_as::<_, (String, NaiveDate)>(query)
+            .bind(from)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Every (asset, UTC date) pair on either side of a swap since `from`
+    pub async fn fetch_swap_asset_dates(
+        &self,
+        from: NaiveDate,
+    ) -> Result<Vec<(String, NaiveDate)>, SqlxError> {
+        let query = r#"
+            SELECT DISTINCT legs.asset, CAST(TO_TIMESTAMP(legs.timestamp) AT TIME ZONE 'UTC' AS DATE)
+            FROM (
+                SELECT source_asset AS asset, timestamp FROM unified_swaps
+                UNION ALL
+                SELECT dest_asset AS asset, timestamp FROM unified_swaps
+            ) AS legs
+            WHERE legs.timestamp >= EXTRACT(EPOCH FROM CAST($1 AS TIMESTAMP))
+        "#;
+
+        sqlx::query_as::<_, (String, NaiveDate)>(query)
+            .bind(from)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn fetch_coin_ids(&self) -> Result<Vec<(String, Option<String>)>, SqlxError> {
+        sqlx::query_as::<_, (String, Option<String>)>(
+            "SELECT asset, coin_id FROM coingecko_coin_ids",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn upsert_coin_id(
+        &self,
+        asset: &str,
+        coin_id: Option<&str>,
+    ) -> Result<(), SqlxError> {
+        let query = r#"
+            INSERT INTO coingecko_coin_ids (asset, coin_id)
+            VALUES ($1, $2)
+            ON CONFLICT (asset) DO UPDATE
+            SET coin_id = EXCLUDED.coin_id, resolved_at = NOW()
+        "#;
+
+        sqlx::query(query)
+            .bind(asset)
+            .bind(coin_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Outer None means the symbol was never searched, inner None that the search found nothing
+    pub async fn fetch_coin_id(&self, asset: &str) -> Result<Option<Option<String>>, SqlxError> {
+        let record = sqlx::query_as::<_, (Option<String>,)>(
+            "SELECT coin_id FROM coingecko_coin_ids WHERE asset = $1",
+        )
+        .bind(asset)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(|(coin_id,)| coin_id))
+    }
+
+    // (asset, USD price, timestamp) for both legs of every priced swap in the table on `date`,
+    // derived from Midgard's inPriceUSD/outPriceUSD, latest first
+    pub async fn fetch_thorchain_leg_prices(
+        &self,
+        table_name: &str,
+        date: NaiveDate,
+    ) -> Result<Vec<(String, f64, i64)>, SqlxError> {
+        let query = format!(
+            r#"
+            SELECT legs.asset, legs.price_usd, legs.timestamp
+            FROM (
+                SELECT in_asset AS asset, in_amount_usd / in_amount AS price_usd,
+                    CAST(timestamp AS BIGINT) AS timestamp
+                FROM {0}
+                WHERE in_amount > 0 AND in_amount_usd > 0
+                UNION ALL
+                SELECT out_asset_1 AS asset, out_amount_usd / out_amount_1 AS price_usd,
+                    CAST(timestamp AS BIGINT) AS timestamp
+                FROM {0}
+                WHERE out_amount_1 > 0 AND out_amount_usd > 0
+            ) AS legs
+            WHERE legs.timestamp >= EXTRACT(EPOCH FROM CAST($1 AS TIMESTAMP))
+                AND legs.timestamp < EXTRACT(EPOCH FROM CAST($1 AS TIMESTAMP) + INTERVAL '1 day')
+            ORDER BY legs.timestamp DESC
+            "#,
+            table_name
+        );
+
+        sqlx::query_as::<_, (String, f64, i64)>(query.as_str())
+            .bind(date)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    // Same as fetch_thorchain_leg_prices, derived from Chainflip's inputValueUsd/outputValueUsd
+    pub async fn fetch_chainflip_leg_prices(
+        &self,
+        date: NaiveDate,
+    ) -> Result<Vec<(String, f64, i64)>, SqlxError> {
+        let query = r#"
+            SELECT legs.asset, legs.price_usd, legs.timestamp
+            FROM (
+                SELECT source_asset AS asset, input_value_usd / input_amount AS price_usd, timestamp
+                FROM chainflip_swaps_detailed
+                WHERE input_amount > 0 AND input_value_usd > 0
+                UNION ALL
+                SELECT dest_asset AS asset, output_value_usd / output_amount AS price_usd, timestamp
+                FROM chainflip_swaps_detailed
+                WHERE output_amount > 0 AND output_value_usd > 0
+            ) AS legs
+            WHERE legs.timestamp >= EXTRACT(EPOCH FROM CAST($1 AS TIMESTAMP))
+                AND legs.timestamp < EXTRACT(EPOCH FROM CAST($1 AS TIMESTAMP) + INTERVAL '1 day')
+            ORDER BY legs.timestamp DESC
+        "#;
+
+        sqlx::query_as::<_, (String, f64, i64)>(query)
+            .bind(date)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn fetch_missing_closing_price_dates(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<NaiveDate>, SqlxError> {
+        let query = r#"
+            SELECT CAST(days.day AS DATE)
+            FROM GENERATE_SERIES(CAST($1 AS DATE), CAST($2 AS DATE), INTERVAL '1 day') AS days (day)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM btc_closing_prices AS prices
+                WHERE CAST(prices.date AS DATE) = CAST(days.day AS DATE)
+            )
+            ORDER BY 1
+        "#;
+
+        let dates = sqlx::query_as::<_, (NaiveDate,)>(query)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(dates.into_iter().map(|(date,)| date).collect())
+    }
+
+    // Dates with swaps that have a BTC leg but no entry in btc_closing_prices yet
+    pub async fn fetch_unpriced_swap_dates(
+        &self,
+        table_name: &str,
+    ) -> Result<Vec<NaiveDate>, SqlxError> {
+        let query = format!(
+            r#"
+            SELECT DISTINCT CAST(swaps.date AS DATE)
+            FROM {} AS swaps
+            WHERE swaps.btc_value_usd IS NULL
+                AND (swaps.in_asset IN {1} OR swaps.out_asset_1 IN {1})
+                AND CAST(swaps.date AS DATE) < CAST(NOW() AT TIME ZONE 'UTC' AS DATE)
+                AND NOT EXISTS (
+                    SELECT 1 FROM btc_closing_prices AS prices
+                    WHERE CAST(prices.date AS DATE) = CAST(swaps.date AS DATE)
+                )
+            ORDER BY 1
+            "#,
+            table_name, BTC_ASSETS
+        );
+
+        let dates = sqlx::query_as::<_, (NaiveDate,)>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(dates.into_iter().map(|(date,)| date).collect())
+    }
+
+    // Values the BTC leg of swaps with the BTC candle for the UTC hour the swap executed in,
+    // falling back to the closing price for the swap's date. Close-priced swaps are revalued
+    // once their candle is stored.
+    pub async fn apply_btc_valuation(
+        &self,
+        table_name: &str,
+    ) -> Result<ValuationSummary, SqlxError> {
+        let query = format!(
+            r#"
+            WITH valued AS (
+                UPDATE {0} AS swaps
+                SET
+                    btc_price_usd = valuation.price,
+                    btc_value_usd = valuation.price * CASE
+                        WHEN swaps.in_asset IN {1} THEN swaps.in_amount
+                        ELSE swaps.out_amount_1
+                    END,
+                    btc_price_source = valuation.source
+                FROM (
+                    SELECT
+                        swaps.tx_id,
+                        COALESCE(candles.close, prices.closing_price_usd) AS price,
+                        CASE WHEN candles.close IS NULL THEN 'close' ELSE 'candle' END AS source
+                    FROM {0} AS swaps
+                    LEFT JOIN price_candles AS candles
+                        ON candles.asset = 'BTC'
+                        AND candles.open_time = DATE_TRUNC(
+                            'hour', TO_TIMESTAMP(swaps.timestamp) AT TIME ZONE 'UTC'
+                        ) AT TIME ZONE 'UTC'
+                    LEFT JOIN btc_closing_prices AS prices
+                        ON CAST(prices.date AS DATE) = CAST(swaps.date AS DATE)
+                    WHERE (swaps.in_asset IN {1} OR swaps.out_asset_1 IN {1})
+                        AND (
+                            swaps.btc_value_usd IS NULL
+                            OR (swaps.btc_price_source = 'close' AND candles.close IS NOT NULL)
+                        )
+                        AND COALESCE(candles.close, prices.closing_price_usd) IS NOT NULL
+                ) AS valuation
+                WHERE swaps.tx_id = valuation.tx_id
+                RETURNING CAST(swaps.date AS DATE) AS date
+            )
+            SELECT COUNT(*), MIN(date) FROM valued
+            "#,
+            table_name, BTC_ASSETS
+        );
+
+        let (valued, earliest_date) = sqlx::query_as::<_, (i64, Option<NaiveDate>)>(&query)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(ValuationSummary {
+            valued: valued as u64,
+            earliest_date,
+        })
+    }
+
+    pub async fn fetch_chainflip_swap_by_id(
+        &self,
+        swap_id: &str,
+    ) -> Result<Option<ChainflipSwapDetailed>, SqlxError> {
+        let query = r#"
+            SELECT
+                CAST(timestamp AS BIGINT) AS timestamp, TO_CHAR(date, 'YYYY-MM-DD') AS date, swap_id,
+                source_asset, dest_asset,
+                base_asset_leg1, base_asset_leg2,
+                ingress_amount, ingress_value_usd,
+                input_amount, input_value_usd,
+                output_amount, output_value_usd,
+                started_block_date, started_block_id, started_block_timestamp,
+                destination_address, refund_address,
+                status, broker
+            FROM chainflip_swaps_detailed
+            WHERE swap_id = $1
+        "#;
+
+        let record = sqlx::query_as::<_, ChainflipSwapDetailed>(query)
+            .bind(swap_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(record)
+    }
+
+    // Fetches one row more than the page size so the caller can tell whether a next page exists
+    pub async fn fetch_unified_swaps(
+        &self,
+        protocol: Option<Protocol>,
+        limit: u64,
+        offset: i64,
+    ) -> Result<Vec<UnifiedSwap>, SqlxError> {
+        let query = r#"
+            SELECT
+                protocol, swap_id, source_asset, dest_asset,
+                source_amount, dest_amount, source_amount_usd, dest_amount_usd,
+                source_address, dest_address, timestamp, status, btc_value_usd
+            FROM unified_swaps
+            WHERE ($1::TEXT IS NULL OR protocol = $1)
+            ORDER BY timestamp DESC, swap_id DESC
+            LIMIT $2 OFFSET $3
+        "#;
+
+        let records = sqlx::query_as::<_, UnifiedSwap>(query)
+            .bind(protocol.map(|p| p.as_str()))
+            .bind(limit as i64 + 1)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    pub async fn count_unified_swaps(&self, protocol: Option<Protocol>) -> Result<i64, SqlxError> {
+        let query = r#"
+            SELECT COUNT(*)
+            FROM unified_swaps
+            WHERE ($1::TEXT IS NULL OR protocol = $1)
+        "#;
+
+        let (total,) = sqlx::query_as::<_, (i64,)>(query)
+            .bind(protocol.map(|p| p.as_str()))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(total)
+    }
+
+    // Fetches one row more than the page size so the caller can tell whether a next page exists
+    pub async fn fetch_address_swaps(
+        &self,
+        address: &str,
+        protocol: Option<Protocol>,
+        limit: u64,
+        offset: i64,
+    ) -> Result<Vec<UnifiedSwap>, SqlxError> {
+        let query = r#"
+            SELECT
+                protocol, swap_id, source_asset, dest_asset,
+                source_amount, dest_amount, source_amount_usd, dest_amount_usd,
+                source_address, dest_address, timestamp, status, btc_value_usd
+            FROM unified_swaps
+            WHERE (source_address = $1 OR dest_address = $1)
+                AND ($2::TEXT IS NULL OR protocol = $2)
+            ORDER BY timestamp DESC, swap_id DESC
+            LIMIT $3 OFFSET $4
+        "#;
+
+        let records = sqlx::query_as::<_, UnifiedSwap>(query)
+            .bind(address)
+            .bind(protocol.map(|p| p.as_str()))
+            .bind(limit as i64 + 1)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    pub async fn count_address_swaps(
+        &self,
+        address: &str,
+        protocol: Option<Protocol>,
+    ) -> Result<i64, SqlxError> {
+        let query = r#"
+            SELECT COUNT(*)
+            FROM unified_swaps
+            WHERE (source_address = $1 OR dest_address = $1)
+                AND ($2::TEXT IS NULL OR protocol = $2)
+        "#;
+
+        let (total,) = sqlx::query_as::<_, (i64,)>(query)
+            .bind(address)
+            .bind(protocol.map(|p| p.as_str()))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(total)
+    }
+
+    pub async fn fetch_address_activity(
+        &self,
+        address: &str,
+        protocol: Option<Protocol>,
+    ) -> Result<AddressActivity, SqlxError> {
+        let query = r#"
+            SELECT
+                COUNT(*) AS swap_count,
+                SUM(source_amount_usd) AS total_in_usd,
+                SUM(dest_amount_usd) AS total_out_usd,
+                MIN(timestamp) AS first_activity,
+                MAX(timestamp) AS last_activity
+            FROM unified_swaps
+            WHERE (source_address = $1 OR dest_address = $1)
+                AND ($2::TEXT IS NULL OR protocol = $2)
+        "#;
+
+        let activity = sqlx::query_as::<_, AddressActivity>(query)
+            .bind(address)
+            .bind(protocol.map(|p| p.as_str()))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(activity)
+    }
+
+    pub async fn fetch_address_asset_volumes(
+        &self,
+        address: &str,
+        protocol: Option<Protocol>,
+    ) -> Result<Vec<AssetVolume>, SqlxError> {
+        let query = r#"
+            WITH matched AS (
+                SELECT source_asset, dest_asset, source_amount, dest_amount
+                FROM unified_swaps
+                WHERE (source_address = $1 OR dest_address = $1)
+                    AND ($2::TEXT IS NULL OR protocol = $2)
+            )
+            SELECT
+                asset,
+                CAST(SUM(volume_in) AS DOUBLE PRECISION) AS volume_in,
+                CAST(SUM(volume_out) AS DOUBLE PRECISION) AS volume_out
+            FROM (
+                SELECT source_asset AS asset, source_amount AS volume_in, 0 AS volume_out FROM matched
+                UNION ALL
+                SELECT dest_asset AS asset, 0 AS volume_in, dest_amount AS volume_out FROM matched
+            ) AS legs
+            GROUP BY asset
+            ORDER BY asset
+        "#;
+
+        let records = sqlx::query_as::<_, AssetVolume>(query)
+            .bind(address)
+            .bind(protocol.map(|p| p.as_str()))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    // Recomputes daily_volume_rollups from unified_swaps for every day from `since` onwards,
+    // or for all history when `since` is None
+    pub async fn refresh_volume_rollups(&self, since: Option<NaiveDate>) -> Result<u64, SqlxError> {
+        let query = format!(
+            r#"
+            INSERT INTO daily_volume_rollups (
+                date, protocol, swap_count, unique_addresses,
+                volume_usd, btc_volume, btc_volume_usd, avg_size_usd, updated_at
+            )
+            SELECT
+                swaps.date,
+                swaps.protocol,
+                COUNT(DISTINCT swaps.swap_id) AS swap_count,
+                COUNT(DISTINCT addresses.address) AS unique_addresses,
+                SUM(swaps.source_amount_usd) FILTER (WHERE addresses.side = 0) AS volume_usd,
+                COALESCE(SUM(swaps.btc_amount) FILTER (WHERE addresses.side = 0), 0) AS btc_volume,
+                SUM(swaps.btc_value_usd) FILTER (WHERE addresses.side = 0) AS btc_volume_usd,
+                SUM(swaps.source_amount_usd) FILTER (WHERE addresses.side = 0)
+                    / COUNT(DISTINCT swaps.swap_id) AS avg_size_usd,
+                NOW()
+            FROM (
+                SELECT
+                    CAST(TO_TIMESTAMP(timestamp) AT TIME ZONE 'UTC' AS DATE) AS date,
+                    protocol, swap_id, source_amount_usd, btc_value_usd, source_address, dest_address,
+                    CASE WHEN source_asset IN {0} THEN source_amount ELSE 0 END
+                        + CASE WHEN dest_asset IN {0} THEN dest_amount ELSE 0 END AS btc_amount
+                FROM unified_swaps
+                WHERE status = 'success'
+                    AND ($1::DATE IS NULL OR timestamp >= EXTRACT(EPOCH FROM CAST($1 AS TIMESTAMP)))
+            ) AS swaps
+            CROSS JOIN LATERAL (
+                VALUES (0, swaps.source_address), (1, swaps.dest_address)
+            ) AS addresses (side, address)
+            GROUP BY swaps.date, swaps.protocol
+            ON CONFLICT (date, protocol) DO UPDATE SET
+                swap_count = EXCLUDED.swap_count,
+                unique_addresses = EXCLUDED.unique_addresses,
+                volume_usd = EXCLUDED.volume_usd,
+                btc_volume = EXCLUDED.btc_volume,
+                btc_volume_usd = EXCLUDED.btc_volume_usd,
+                avg_size_usd = EXCLUDED.avg_size_usd,
+                updated_at = EXCLUDED.updated_at
+            "#,
+            BTC_ASSETS
+        );
+
+        let result = sqlx::query(&query).bind(since).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    // Sums the daily rollups into day/week/month buckets. Unique addresses can't be summed
+    // across days, so for week and month they are counted from unified_swaps directly.
+    pub async fn fetch_volume_stats(
+        &self,
+        query: &VolumeStatsQuery,
+    ) -> Result<Vec<VolumeStat>, SqlxError> {
+        let sql = r#"
+            WITH totals AS (
+                SELECT
+                    CAST(DATE_TRUNC($1, CAST(date AS TIMESTAMP)) AS DATE) AS period_start,
+                    protocol,
+                    CAST(SUM(swap_count) AS BIGINT) AS swap_count,
+                    CAST(SUM(unique_addresses) AS BIGINT) AS daily_unique_addresses,
+                    SUM(volume_usd) AS volume_usd,
+                    SUM(btc_volume) AS btc_volume,
+                    SUM(btc_volume_usd) AS btc_volume_usd
+                FROM daily_volume_rollups
+                WHERE date >= $2 AND date <= $3
+                    AND ($4::TEXT IS NULL OR protocol = $4)
+                GROUP BY 1, 2
+            ),
+            uniques AS (
+                SELECT
+                    CAST(DATE_TRUNC($1, TO_TIMESTAMP(swaps.timestamp) AT TIME ZONE 'UTC') AS DATE) AS period_start,
+                    swaps.protocol,
+                    COUNT(DISTINCT addresses.address) AS unique_addresses
+                FROM unified_swaps AS swaps
+                CROSS JOIN LATERAL (
+                    VALUES (swaps.source_address), (swaps.dest_address)
+                ) AS addresses (address)
+                WHERE $1 <> 'day'
+                    AND swaps.status = 'success'
+                    AND swaps.timestamp >= EXTRACT(EPOCH FROM CAST($2 AS TIMESTAMP))
+                    AND swaps.timestamp < EXTRACT(EPOCH FROM CAST($3 AS TIMESTAMP) + INTERVAL '1 day')
+                    AND ($4::TEXT IS NULL OR swaps.protocol = $4)
+                GROUP BY 1, 2
+            )
+            SELECT
+                totals.period_start,
+                totals.protocol,
+                totals.swap_count,
+                COALESCE(uniques.unique_addresses, totals.daily_unique_addresses) AS unique_addresses,
+                totals.volume_usd,
+                totals.btc_volume,
+                totals.btc_volume_usd,
+                totals.volume_usd / NULLIF(totals.swap_count, 0) AS avg_size_usd
+            FROM totals
+            LEFT JOIN uniques USING (period_start, protocol)
+            ORDER BY totals.period_start, totals.protocol
+        "#;
+
+        let records = sqlx::query_as::<_, VolumeStat>(sql)
+            .bind(query.interval.as_str())
+            .bind(query.from)
+            .bind(query.to)
+            .bind(query.protocol.map(|p| p.as_str()))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(records)
+    }
+
+    // WHERE clause shared by fetch_chainflip_swaps and count_chainflip_swaps, numbered from $1
+    fn chainflip_swap_filters(query: &ChainflipSwapsQuery) -> (String, usize) {
+        let mut clauses = String::from("WHERE (1 = 1)");
+        let mut param = 1;
+        let filters = [
+            (query.source_asset.is_some(), "source_asset ="),
+            (query.dest_asset.is_some(), "dest_asset ="),
+            (query.broker.is_some(), "broker ="),
+            (query.status.is_some(), "status ="),
+            (query.from.is_some(), "date >="),
+            (query.to.is_some(), "date <="),
+        ];
+        for (_, condition) in filters.iter().filter(|(enabled, _)| *enabled) {
+            clauses.push_str(&format!(" AND {} ${}", condition, param));
+            param += 1;
+        }
+        if query.address.is_some() {
+            clauses.push_str(&format!(
+                " AND (destination_address = ${0} OR refund_address = ${0})",
+                param
+            ));
+            param += 1;
+        }
+        (clauses, param)
+    }
+
+    fn bind_chainflip_swap_filters<'q, O>(
+        mut sql_query: sqlx::query::QueryAs<'q, sqlx::Postgres, O, sqlx::postgres::PgArguments>,
+        query: &ChainflipSwapsQuery,
+    ) -> sqlx::query::QueryAs<'q, sqlx::Postgres, O, sqlx::postgres::PgArguments> {
+        for value in [
+            &query.source_asset,
+            &query.dest_asset,
+            &query.broker,
+            &query.status,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            sql_query = sql_query.bind(value.clone());
+        }
+        for date in [query.from, query.to].into_iter().flatten() {
+            sql_query = sql_query.bind(date);
         }
-        let records = query.fetch_all(&self.pool).await?;
+        if let Some(address) = &query.address {
+            sql_query = sql_query.bind(address.clone());
+        }
+        sql_query
+    }
+
+    pub async fn fetch_chainflip_swaps(
+        &self,
+        query: &ChainflipSwapsQuery,
+    ) -> Result<Vec<ChainflipSwapDetailed>, SqlxError> {
+        let (filters, next_param) = Self::chainflip_swap_filters(query);
+        let order = match query.order {
+            OrderType::ASC => "ASC",
+            OrderType::DESC => "DESC",
+        };
+        let base_query = format!(
+            r#"
+            SELECT
+                CAST(timestamp AS BIGINT) AS timestamp, TO_CHAR(date, 'YYYY-MM-DD') AS date, swap_id,
+                source_asset, dest_asset,
+                base_asset_leg1, base_asset_leg2,
+                ingress_amount, ingress_value_usd,
+                input_amount, input_value_usd,
+                output_amount, output_value_usd,
+                started_block_date, started_block_id, started_block_timestamp,
+                destination_address, refund_address,
+                status, broker
+            FROM chainflip_swaps_detailed
+            {}
+            ORDER BY {} {}, id {}
+            LIMIT ${} OFFSET ${}
+            "#,
+            filters,
+            query.sort_by.column(),
+            order,
+            order,
+            next_param,
+            next_param + 1,
+        );
+
+        let sql_query = sqlx::query_as::<_, ChainflipSwapDetailed>(&base_query);
+        let records = Self::bind_chainflip_swap_filters(sql_query, query)
+            .bind(query.limit as i64 + 1)
+            .bind(query.offset())
+            .fetch_all(&self.pool)
+            .await?;
+
         Ok(records)
     }
 
+    pub async fn count_chainflip_swaps(
+        &self,
+        query: &ChainflipSwapsQuery,
+    ) -> Result<i64, SqlxError> {
+        let (filters, _) = Self::chainflip_swap_filters(query);
+        let count_query = format!("SELECT COUNT(*) FROM chainflip_swaps_detailed {}", filters);
+
+        let sql_query = sqlx::query_as::<_, (i64,)>(&count_query);
+        let (total,) = Self::bind_chainflip_swap_filters(sql_query, query)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(total)
+    }
+
     pub async fn insert_chainflip_swap_detailed(
         &self,
         record: ChainflipSwapDetailed,
@@ -1,296 +1,601 @@
-use std::collections::HashSet;
-use std::sync::Arc;
-
-use crate::db::PostgreSQL;
-use crate::models::actions_model::SwapTransactionFromatted;
-use crate::models::chainflip_swaps::{ChainflipSwap, ChainflipSwapDetailed};
-use crate::models::closing_prices::ClosingPriceInterval;
-use crate::utils::coingecko::CoinGecko;
+use crate::config::SourceConfig;
+use crate::db::{PostgreSQL, ValuationSummary};
+use crate::models::actions_model::{SwapTransaction, SwapTransactionFromatted};
+use crate::models::chainflip_swaps::{ChainflipSwap, ChainflipSwapDetailed, SwapNode};
+use crate::models::closing_prices::{AssetClosingPrice, ClosingPriceInterval};
+use crate::models::sync_cursors::SyncDirection;
+use crate::utils::chainflip::ChainFlip;
+use crate::utils::coingecko::SharedCoinGecko;
 use crate::utils::midgard::MidGard;
+use crate::utils::pending_tracker::{PendingTracker, RetryPolicy};
+use crate::utils::price_provider::PriceProviderChain;
 use crate::utils::transaction_handler::{TransactionError, TransactionHandler};
-use crate::utils::{parse_f64, read_next_page_token_from_file, write_next_page_token_to_file};
-use crate::SwapType;
-use chrono::Utc;
-use futures_util::lock::Mutex;
-
-pub async fn fetch_btc_closing_price(pg: &PostgreSQL) -> Result<(), TransactionError> {
-    let coingecko = match CoinGecko::init() {
-        Ok(coingecko) => coingecko,
-        Err(err) => {
-            println!("Error Initializing CoinGecko : {:?}", err);
-            return Err(TransactionError::ApiError(String::from(
-                "Error Initializing CoinGecko",
-            )));
-        }
-    };
+use crate::utils::{hourly_candles, parse_f64, price_symbol};
+use chrono::{NaiveDate, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
-    let btc_coin_id = "bitcoin";
-    let today = Utc::now();
-    let coingecko_date = today.format("%d-%m-%Y").to_string();
-    let current_date = today.format("%Y-%m-%d").to_string();
+const CANDLE_WINDOW_DAYS: i64 = 90;
 
-    let closing_price_usd = match coingecko
-        .fetch_usd_price(&btc_coin_id, &coingecko_date)
-        .await
-    {
-        Ok(closing_price) => closing_price,
-        Err(err) => {
-            println!("Error Fetching Closing Price : {:?}", err);
-            return Err(TransactionError::PriceFetchError(btc_coin_id.to_string()));
+// Outcome of a price fetch; `failed` counts prices or assets that could not be fetched or stored
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PriceFetchSummary {
+    pub stored: usize,
+    pub failed: usize,
+}
+
+impl PriceFetchSummary {
+    fn record<T, E: std::fmt::Display>(&mut self, result: Result<T, E>, context: &str) {
+        match result {
+            Ok(_) => self.stored += 1,
+            Err(err) => {
+                println!("{}: {}", context, err);
+                self.failed += 1;
+            }
         }
-    };
+    }
+}
+
+pub async fn fetch_btc_closing_price(
+    pg: &PostgreSQL,
+    prices: &PriceProviderChain,
+) -> Result<(), TransactionError> {
+    store_btc_closing_price(pg, prices, last_closed_date()).await?;
+    Ok(())
+}
+
+// Fetches the BTC close for `date` and stores it in btc_closing_prices and closing_prices
+pub async fn store_btc_closing_price(
+    pg: &PostgreSQL,
+    prices: &PriceProviderChain,
+    date: NaiveDate,
+) -> Result<f64, TransactionError> {
+    let current_date = date.format("%Y-%m-%d").to_string();
+
+    let price = prices.closing_price("BTC", date).await?;
+    let closing_price_usd = price.price_usd;
 
     let closing_price_interval = ClosingPriceInterval {
         date: current_date.clone(),
         closing_price_usd,
+        provider: price.provider.to_string(),
     };
-    match pg.insert_closing_price(closing_price_interval).await {
-        Ok(_) => {
-            println!(
-                "Closing Price Inserted Successfully for Date : {} Price : {}",
-                &current_date, &closing_price_usd
-            );
+    pg.insert_closing_price(closing_price_interval).await?;
+
+    let asset_closing_price = AssetClosingPrice {
+        asset: "BTC".to_string(),
+        date,
+        coin_id: price.coin_id,
+        closing_price_usd,
+        provider: price.provider.to_string(),
+    };
+    pg.insert_asset_closing_price(asset_closing_price).await?;
+
+    println!(
+        "Closing Price Inserted Successfully for Date : {} Price : {} ({})",
+        &current_date, &closing_price_usd, price.provider
+    );
+    Ok(closing_price_usd)
+}
+
+// Fills every date between `from` and `to` (inclusive) that has no row in btc_closing_prices
+pub async fn backfill_btc_closing_prices(
+    pg: &PostgreSQL,
+    prices: &PriceProviderChain,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<PriceFetchSummary, TransactionError> {
+    let missing_dates = pg.fetch_missing_closing_price_dates(from, to).await?;
+    println!(
+        "Backfilling {} Missing Closing Prices between {} and {}",
+        missing_dates.len(),
+        from,
+        to
+    );
+    store_btc_closing_prices(pg, prices, missing_dates).await
+}
+
+// Fetches prices one date at a time; providers pace their own requests
+async fn store_btc_closing_prices(
+    pg: &PostgreSQL,
+    prices: &PriceProviderChain,
+    dates: Vec<NaiveDate>,
+) -> Result<PriceFetchSummary, TransactionError> {
+    let mut summary = PriceFetchSummary::default();
+    for date in dates {
+        summary.record(
+            store_btc_closing_price(pg, prices, date).await,
+            &format!("Giving up on closing price for {}", date),
+        );
+    }
+    Ok(summary)
+}
+
+// Prices every asset seen in the swap tables since `from` for each day it was swapped, trying
+// each price provider in turn
+pub async fn fetch_asset_closing_prices(
+    pg: &PostgreSQL,
+    prices: &PriceProviderChain,
+    from: NaiveDate,
+) -> Result<PriceFetchSummary, TransactionError> {
+    let existing: HashSet<(String, NaiveDate)> = pg
+        .fetch_closing_price_keys(from)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut missing: BTreeMap<String, BTreeSet<NaiveDate>> = BTreeMap::new();
+    for (asset, date) in pg.fetch_swap_asset_dates(from).await? {
+        if date > last_closed_date() {
+            continue;
         }
-        Err(err) => {
-            println!("Error Inserting Closing Price : {:?}", err);
+        let symbol = match price_symbol(&asset) {
+            Some(symbol) => symbol,
+            None => continue,
+        };
+        if !existing.contains(&(symbol.clone(), date)) {
+            missing.entry(symbol).or_default().insert(date);
         }
     }
 
-    Ok(())
-}
-pub async fn _fetch_historical_data(
-    base_url: &str,
-    swap_type: SwapType,
-) -> Result<(), TransactionError> {
-    println!("Starting..");
-    let pg = PostgreSQL::init().await.map_err(|e| {
-        TransactionError::DatabaseError(format!("Error connecting to PostgreSQL: {:?}", e))
-    })?;
-    let transaction_handler = TransactionHandler;
-    const TOKEN_FILE_PATH: &str = "next_page_token.txt";
-    let mut next_page_token = read_next_page_token_from_file(TOKEN_FILE_PATH).unwrap_or_default();
-    let mut transaction_batch: Vec<SwapTransactionFromatted> = Vec::new();
-    let mut batch_count = 0;
-    loop {
-        let resp =
-            match MidGard::fetch_actions_with_nextpage(base_url, next_page_token.as_str()).await {
-                Ok(resp) => resp,
+    let mut summary = PriceFetchSummary::default();
+    for (symbol, dates) in missing {
+        println!("Fetching {} Missing {} Closing Prices", dates.len(), symbol);
+        for date in dates {
+            match prices.closing_price(&symbol, date).await {
+                Ok(price) => {
+                    let record = AssetClosingPrice {
+                        asset: symbol.clone(),
+                        date,
+                        coin_id: price.coin_id,
+                        closing_price_usd: price.price_usd,
+                        provider: price.provider.to_string(),
+                    };
+                    summary.record(
+                        pg.insert_asset_closing_price(record).await,
+                        &format!("Error Inserting {} Closing Price", symbol),
+                    );
+                }
                 Err(err) => {
-                    println!("Error fetching actions data: {:?}. Retrying...", err);
-                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                    continue;
+                    println!(
+                        "Giving up on {} closing price for {}: {}",
+                        symbol, date, err
+                    );
+                    summary.failed += 1;
                 }
-            };
+            }
+        }
+    }
+    Ok(summary)
+}
 
-        if resp.actions.is_empty() {
-            println!("No more actions to process, exiting loop.");
-            break;
+// Most recent UTC day with a known close
+pub fn last_closed_date() -> NaiveDate {
+    Utc::now().date_naive() - chrono::Duration::days(1)
+}
+
+// Stores hourly candles for every asset with a CoinGecko id, continuing from each asset's
+// latest candle. market_chart/range only returns hourly points for ranges up to 90 days.
+pub async fn fetch_price_candles(
+    pg: &PostgreSQL,
+    coingecko: Option<&SharedCoinGecko>,
+) -> Result<PriceFetchSummary, TransactionError> {
+    let coingecko = match coingecko {
+        Some(coingecko) => coingecko,
+        None => {
+            println!("CoinGecko is not configured, skipping price candles");
+            return Ok(PriceFetchSummary::default());
         }
+    };
+    let now = Utc::now();
+    let earliest = now - chrono::Duration::days(CANDLE_WINDOW_DAYS);
+    let mut summary = PriceFetchSummary::default();
+
+    for (asset, coin_id) in pg.fetch_coin_ids().await? {
+        let coin_id = match coin_id {
+            Some(coin_id) => coin_id,
+            None => continue,
+        };
+        // Restart from the latest candle's open so a partially filled hour is rebuilt
+        let from = match pg.fetch_latest_candle_time(&asset).await? {
+            Some(latest) if latest > earliest => latest,
+            _ => earliest,
+        };
 
-        let processed_transactions = transaction_handler
-            .process_transactions(&resp.actions, swap_type.clone())
-            .await;
-        match processed_transactions {
-            Ok(val) => {
-                transaction_batch.extend(val);
-                batch_count += 1;
-                println!("Processed Batch : {}", &batch_count);
-                next_page_token = resp.meta.nextPageToken.clone();
-                if let Err(e) = write_next_page_token_to_file(&next_page_token, TOKEN_FILE_PATH) {
-                    return Err(TransactionError::FileError(format!(
-                        "Error writing next page token to file: {:?}",
-                        e
-                    )));
+        let (points, delay) = {
+            let coingecko = coingecko.read().await;
+            let points = coingecko
+                .fetch_market_chart_range(&coin_id, from.timestamp(), now.timestamp())
+                .await;
+            (points, coingecko.request_delay())
+        };
+        match points {
+            Ok(points) => {
+                for candle in hourly_candles(&asset, &points) {
+                    summary.record(
+                        pg.insert_price_candle(candle).await,
+                        &format!("Error Inserting {} Candle", asset),
+                    );
                 }
             }
             Err(err) => {
-                println!("Error parsing Transactions : {:?}", err);
-                return Err(TransactionError::ProcessingError(format!(
-                    "Error processing transaction: {:?}",
-                    err
-                )));
+                println!("Error Fetching {} Market Chart : {:?}", asset, err);
+                summary.failed += 1;
             }
         }
+        tokio::time::sleep(delay).await;
+    }
+    Ok(summary)
+}
 
-        if batch_count >= 20 {
-            let table_name = match swap_type {
-                SwapType::NATIVE => "native_swaps_thorchain",
-                SwapType::TRADE => "swap_history_test",
-            };
-            let insertion_response = pg.insert_bulk(table_name, transaction_batch.clone()).await;
-            match insertion_response {
-                Ok(_) => {
-                    println!(
-                        "Batch insertion Successfull of : {}",
-                        &transaction_batch.len()
-                    );
-                }
-                Err(err) => {
-                    println!("Error inserting Batch : {:?}", err);
-                }
-            }
-            batch_count = 0;
-            transaction_batch.clear();
+// Backfills closing prices for swap dates that have none, then values the BTC leg of every
+// swap in the table that hasn't been valued yet
+pub async fn value_thorchain_swaps(
+    pg: &PostgreSQL,
+    prices: &PriceProviderChain,
+    table: &str,
+    label: &str,
+) -> Result<ValuationSummary, TransactionError> {
+    let missing_dates = pg.fetch_unpriced_swap_dates(table).await?;
+    if !missing_dates.is_empty() {
+        println!(
+            "Backfilling {} Missing Closing Prices for {}",
+            missing_dates.len(),
+            label
+        );
+        let summary = store_btc_closing_prices(pg, prices, missing_dates).await?;
+        if summary.failed > 0 {
             println!(
-                "Batch Cleared. Size After Clear: {}",
-                &transaction_batch.len()
+                "Could not price {} dates for {}; only swaps with a candle are valued",
+                summary.failed, label
             );
         }
-
-        // let process_response =
-        //     transaction_handler.process_and_insert_transaction(&pg, &resp.actions).await;
-
-        // match process_response {
-        //     Ok(_) => {
-        //         next_page_token = resp.meta.nextPageToken.clone();
-        //         if let Err(e) = write_next_page_token_to_file(&next_page_token,TOKEN_FILE_PATH) {
-        //             return Err(TransactionError::FileError(format!(
-        //                 "Error writing next page token to file: {:?}",
-        //                 e
-        //             )));
-        //         }
-        //         println!("Updated next page token: {}", &next_page_token);
-        //     }
-        //     Err(err) => {
-        //         return Err(TransactionError::ProcessingError(format!(
-        //             "Error processing transaction: {:?}",
-        //             err
-        //         )));
-        //     }
-        // }
     }
 
-    Ok(())
+    let summary = pg.apply_btc_valuation(table).await?;
+    println!("Valued {} {} Swaps", summary.valued, label);
+    Ok(summary)
 }
-pub async fn fetch_latest_data(
+
+// Recomputes the daily volume rollups from `since`, or every day when it is None
+pub async fn refresh_volume_rollups(
     pg: &PostgreSQL,
-    base_url: &str,
-    swap_type: SwapType,
+    since: Option<NaiveDate>,
 ) -> Result<(), TransactionError> {
-    let transaction_handler = TransactionHandler;
-    let table_name = match swap_type {
-        SwapType::NATIVE => "native_swaps_thorchain",
-        SwapType::TRADE => "swap_history_test",
-    };
+    println!("Refreshing Volume Rollups");
+    let rows = pg.refresh_volume_rollups(since).await?;
+    println!("Volume Rollups Refreshed : {} rows", rows);
+    Ok(())
+}
 
-    // These tables use i64 (INT8) for timestamps
-    let latest_timestamp = match pg.fetch_latest_timestamp_i64(table_name).await {
-        Ok(Some(timestamp)) => timestamp,
-        Ok(None) => Utc::now().timestamp() as i64,
-        Err(err) => {
-            return Err(TransactionError::DatabaseError(format!(
-                "Error fetching the latest timestamp: {:?}",
-                err
-            )));
+// Bounds for backfill_swaps; timestamps are unix seconds and batch_size counts Midgard pages
+#[derive(Debug, Clone)]
+pub struct BackfillOptions {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub batch_size: usize,
+    pub restart: bool,
+}
+
+fn action_timestamp(action: &SwapTransaction) -> Option<i64> {
+    action
+        .date
+        .parse::<i64>()
+        .ok()
+        .map(|nanos| nanos / 1_000_000_000)
+}
+
+// Walks the source's history with nextPageToken, newest first, from `to` (or the saved backward
+// cursor) down to `from`. Every batch is inserted in the same transaction that advances the
+// cursor, so an interrupted backfill resumes where it stopped. Returns the number of new swaps.
+pub async fn backfill_swaps(
+    pg: &PostgreSQL,
+    midgard: &MidGard,
+    source: &SourceConfig,
+    options: &BackfillOptions,
+) -> Result<u64, TransactionError> {
+    let transaction_handler = TransactionHandler::new(PendingTracker::postgres(pg.clone()));
+    let base_url = source.base_url();
+    let source_label = source.label();
+
+    let cursor = if options.restart {
+        None
+    } else {
+        pg.fetch_sync_cursor(&source.name, SyncDirection::Backward)
+            .await?
+    };
+    // Token of the page being processed; empty for the first page of a fresh backfill
+    let mut page_token = match cursor.and_then(|cursor| cursor.token) {
+        Some(token) if !token.is_empty() => {
+            println!("Resuming {} Backfill from cursor {}", source_label, token);
+            token
+        }
+        _ => {
+            println!("Starting {} Backfill", source_label);
+            String::new()
         }
     };
 
-    let pg_clone = pg.clone();
-    let latest_timestamp_str = latest_timestamp.to_string();
-
-    // Fetch actions with the latest timestamp
-    let mut resp =
-        match MidGard::fetch_actions_with_timestamp(base_url, &latest_timestamp_str).await {
-            Ok(response) => response,
-            Err(err) => {
-                return Err(TransactionError::ApiError(format!(
-                    "Error fetching actions with timestamp: {:?}",
-                    err
-                )));
+    let mut transaction_batch: Vec<SwapTransactionFromatted> = Vec::new();
+    let mut batch_pages = 0;
+    let mut total_pages = 0;
+    let mut total_inserted = 0;
+    let mut total_conflicts = 0;
+    let mut upper_bound = options.to;
+    loop {
+        let resp = match (page_token.is_empty(), options.to) {
+            (true, Some(to)) => {
+                midgard
+                    .fetch_actions_until(&base_url, &to.to_string())
+                    .await
+            }
+            _ => {
+                midgard
+                    .fetch_actions_with_nextpage(&base_url, &page_token)
+                    .await
             }
         };
-    let mut actions = resp.actions.clone();
-    actions.reverse();
-    let process_response = transaction_handler
-        .process_and_insert_transaction(&pg_clone, &actions, swap_type.clone())
-        .await;
-    match process_response {
-        Ok(_) => (),
-        Err(err) => {
-            return Err(TransactionError::ProcessingError(format!(
-                "Error processing transaction: {:?}",
-                err
-            )));
+        let mut resp = resp.map_err(|err| {
+            TransactionError::ApiError(format!("Error fetching actions data: {:?}", err))
+        })?;
+
+        if upper_bound.is_none() {
+            upper_bound = resp.actions.first().and_then(action_timestamp);
+        }
+        // Actions are newest first, so everything past the first one below `from` is too
+        let in_range = resp
+            .actions
+            .iter()
+            .take_while(|action| match (options.from, action_timestamp(action)) {
+                (Some(from), Some(timestamp)) => timestamp >= from,
+                _ => true,
+            })
+            .count();
+        let reached_lower_bound = in_range < resp.actions.len();
+        resp.actions.truncate(in_range);
+
+        if !resp.actions.is_empty() {
+            let processed = transaction_handler
+                .process_transactions(&resp.actions, source)
+                .await?;
+            transaction_batch.extend(processed);
+            batch_pages += 1;
+            total_pages += 1;
         }
-    };
 
-    while !resp.actions.is_empty() {
-        let prev_page_token = resp.meta.prevPageToken.clone();
-        resp = match MidGard::fetch_actions_with_prevpage(base_url, prev_page_token.as_str()).await
-        {
-            Ok(response) => response,
-            Err(err) => {
-                return Err(TransactionError::ApiError(format!(
-                    "Error fetching previous page actions: {:?}",
-                    err
-                )));
-            }
+        let finished =
+            reached_lower_bound || resp.actions.is_empty() || resp.meta.nextPageToken.is_empty();
+        // A page cut short at the lower bound is checkpointed itself, so a later backfill with
+        // an earlier `from` picks up its remaining actions
+        let checkpoint = if reached_lower_bound {
+            page_token.clone()
+        } else {
+            resp.meta.nextPageToken.clone()
         };
 
-        let process_response = transaction_handler
-            .process_and_insert_transaction(&pg_clone, &resp.actions, swap_type.clone())
-            .await;
-        match process_response {
-            Ok(_) => (),
-            Err(err) => {
-                return Err(TransactionError::ProcessingError(format!(
-                    "Error processing transaction: {:?}",
+        if batch_pages >= options.batch_size || (finished && batch_pages > 0) {
+            let oldest_timestamp = transaction_batch
+                .iter()
+                .map(|transaction| transaction.timestamp)
+                .min();
+            let summary = pg
+                .insert_bulk_with_cursor(
+                    &source.table,
+                    std::mem::take(&mut transaction_batch),
+                    &source.name,
+                    SyncDirection::Backward,
+                    &checkpoint,
+                    oldest_timestamp,
+                )
+                .await?;
+            total_inserted += summary.inserted;
+            total_conflicts += summary.conflicts;
+            batch_pages = 0;
+
+            let progress = match (options.from, upper_bound, oldest_timestamp) {
+                (Some(from), Some(to), Some(oldest)) if to > from => {
+                    format!(
+                        " ({:.1}%)",
+                        (to - oldest) as f64 / (to - from) as f64 * 100.0
+                    )
+                }
+                _ => String::new(),
+            };
+            println!(
+                "{} Backfill : {} pages, {} new swaps, {} already stored, reached {}{}",
+                source_label,
+                total_pages,
+                total_inserted,
+                total_conflicts,
+                oldest_timestamp
+                    .map(|timestamp| timestamp.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                progress
+            );
+        }
+
+        if finished {
+            break;
+        }
+        page_token = resp.meta.nextPageToken.clone();
+    }
+
+    println!(
+        "{} Backfill Complete : {} pages, {} new swaps, {} already stored",
+        source_label, total_pages, total_inserted, total_conflicts
+    );
+    Ok(total_inserted)
+}
+
+// Polls for swaps newer than the stored forward cursor, falling back to the newest stored swap
+// when there is none. Each page is inserted in the same transaction that advances the cursor to
+// its prevPageToken, so the next poll resumes after the last page written.
+pub async fn fetch_latest_data(
+    pg: &PostgreSQL,
+    midgard: &MidGard,
+    pending_tracker: &PendingTracker,
+    source: &SourceConfig,
+) -> Result<(), TransactionError> {
+    let transaction_handler = TransactionHandler::new(pending_tracker.clone());
+    let base_url = source.base_url();
+
+    let cursor = pg
+        .fetch_sync_cursor(&source.name, SyncDirection::Forward)
+        .await?;
+    let mut resp = match cursor.and_then(|cursor| cursor.token) {
+        Some(token) if !token.is_empty() => {
+            println!("Resuming {} from cursor {}", source.label(), token);
+            midgard.fetch_actions_with_prevpage(&base_url, &token).await
+        }
+        _ => {
+            // These tables use i64 (INT8) for timestamps
+            let latest_timestamp = match pg.fetch_latest_timestamp_i64(&source.table).await {
+                Ok(Some(timestamp)) => timestamp,
+                Ok(None) => Utc::now().timestamp(),
+                Err(err) => {
+                    return Err(TransactionError::DatabaseError(format!(
+                        "Error fetching the latest timestamp: {:?}",
+                        err
+                    )));
+                }
+            };
+            midgard
+                .fetch_actions_with_timestamp(&base_url, &latest_timestamp.to_string())
+                .await
+        }
+    }
+    .map_err(|err| TransactionError::ApiError(format!("Error fetching actions: {:?}", err)))?;
+
+    let mut total_inserted = 0;
+    while !resp.actions.is_empty() {
+        let page_token = resp.meta.prevPageToken.clone();
+        let newest_timestamp = resp.actions.iter().filter_map(action_timestamp).max();
+        let mut actions = resp.actions;
+        actions.reverse();
+        let processed = transaction_handler
+            .process_transactions(&actions, source)
+            .await?;
+        let summary = pg
+            .insert_bulk_with_cursor(
+                &source.table,
+                processed,
+                &source.name,
+                SyncDirection::Forward,
+                &page_token,
+                newest_timestamp,
+            )
+            .await?;
+        total_inserted += summary.inserted;
+
+        resp = midgard
+            .fetch_actions_with_prevpage(&base_url, &page_token)
+            .await
+            .map_err(|err| {
+                TransactionError::ApiError(format!(
+                    "Error fetching previous page actions: {:?}",
                     err
-                )));
-            }
-        };
+                ))
+            })?;
     }
 
-    println!("Latest Data Updated at : {}", latest_timestamp_str);
+    println!(
+        "Latest Data Updated for {} : {} new swaps",
+        source.label(),
+        total_inserted
+    );
     Ok(())
 }
+
 pub async fn retry_pending_transactions(
     pg: &PostgreSQL,
-    base_url: &str,
-    pending_ids: Arc<Mutex<HashSet<String>>>,
-    swap_type: SwapType,
+    midgard: &MidGard,
+    pending_tracker: &PendingTracker,
+    source: &SourceConfig,
+    retry_policy: &RetryPolicy,
 ) -> Result<(), TransactionError> {
-    let transaction_handler = TransactionHandler;
-    let pending_txn_ids = pending_ids.lock().await.clone();
-    println!("Fetching Pending Transactions.. : {:?}", &pending_txn_ids);
-
-    for transaction_id in pending_txn_ids {
-        let resp = match MidGard::fetch_action_with_transactionid(base_url, transaction_id).await {
+    let transaction_handler = TransactionHandler::new(pending_tracker.clone());
+    let base_url = source.base_url();
+    let pending_swaps = pending_tracker.due_for_retry(source, retry_policy).await?;
+    println!("Fetching Pending Transactions.. : {}", pending_swaps.len());
+
+    let mut settled = Vec::new();
+    for pending in pending_swaps {
+        let tx_id = pending.tx_id;
+        let resp = match midgard
+            .fetch_action_with_transactionid(&base_url, tx_id.clone())
+            .await
+        {
             Ok(response) => response,
             Err(err) => {
-                return Err(TransactionError::ApiError(format!(
-                    "Error fetching previous page actions: {:?}",
-                    err
-                )));
+                let error = format!("Error fetching transaction: {:?}", err);
+                pending_tracker
+                    .record_attempt(&tx_id, source, Some(&error))
+                    .await?;
+                continue;
             }
         };
-        let pg_clone = pg.clone();
-        let process_response = transaction_handler
-            .process_and_insert_transaction(&pg_clone, &resp.actions, swap_type.clone())
-            .await;
-        match process_response {
-            Ok(_) => (),
+
+        let swap = match resp.actions.first() {
+            Some(swap) => swap,
+            None => {
+                pending_tracker
+                    .record_attempt(&tx_id, source, Some("Transaction not found"))
+                    .await?;
+                continue;
+            }
+        };
+
+        let transaction_info = match transaction_handler.parse_transaction(swap).await {
+            Ok(val) => val,
             Err(err) => {
-                return Err(TransactionError::ProcessingError(format!(
-                    "Error processing transaction: {:?}",
-                    err
-                )));
+                let error = err.to_string();
+                pending_tracker
+                    .record_attempt(&tx_id, source, Some(&error))
+                    .await?;
+                continue;
             }
         };
+
+        if transaction_info.status != "success" {
+            pending_tracker.record_attempt(&tx_id, source, None).await?;
+            continue;
+        }
+
+        settled.push(transaction_info);
+    }
+
+    let tx_ids: Vec<String> = settled.iter().map(|swap| swap.tx_id.clone()).collect();
+    if let Err(err) = pg.insert_bulk(&source.table, settled).await {
+        let error = format!("Error inserting transaction: {:?}", err);
+        for tx_id in &tx_ids {
+            pending_tracker
+                .record_attempt(tx_id, source, Some(&error))
+                .await?;
+        }
+        return Ok(());
+    }
+
+    for tx_id in tx_ids {
+        pending_tracker.settle(&tx_id, source).await?;
+        println!("Pending Transaction Settled : {}", &tx_id);
     }
     Ok(())
 }
 pub async fn fetch_daily_data(
     pg: &PostgreSQL,
-    base_url: &str,
-    swap_type: SwapType,
+    midgard: &MidGard,
+    pending_tracker: &PendingTracker,
+    source: &SourceConfig,
     day_start_timestamp: i64,
 ) -> Result<(), TransactionError> {
-    let transaction_handler = TransactionHandler;
+    let transaction_handler = TransactionHandler::new(pending_tracker.clone());
+    let base_url = source.base_url();
     let pg_clone = pg.clone();
     let start_timestamp = day_start_timestamp.to_string();
 
-    let mut resp = match MidGard::fetch_actions_with_timestamp(base_url, &start_timestamp).await {
+    let mut resp = match midgard
+        .fetch_actions_with_timestamp(&base_url, &start_timestamp)
+        .await
+    {
         Ok(response) => response,
         Err(err) => {
             return Err(TransactionError::ApiError(format!(
@@ -302,7 +607,7 @@ pub async fn fetch_daily_data(
     let mut actions = resp.actions.clone();
     actions.reverse();
     let process_response = transaction_handler
-        .process_and_insert_transaction(&pg_clone, &actions, swap_type.clone())
+        .process_and_insert_transaction(&pg_clone, &actions, source)
         .await;
     match process_response {
         Ok(_) => (),
@@ -316,7 +621,9 @@ pub async fn fetch_daily_data(
 
     while !resp.actions.is_empty() {
         let prev_page_token = resp.meta.prevPageToken.clone();
-        resp = match MidGard::fetch_actions_with_prevpage(base_url, prev_page_token.as_str()).await
+        resp = match midgard
+            .fetch_actions_with_prevpage(&base_url, prev_page_token.as_str())
+            .await
         {
             Ok(response) => response,
             Err(err) => {
@@ -328,7 +635,7 @@ pub async fn fetch_daily_data(
         };
 
         let process_response = transaction_handler
-            .process_and_insert_transaction(&pg_clone, &resp.actions, swap_type.clone())
+            .process_and_insert_transaction(&pg_clone, &resp.actions, source)
             .await;
         match process_response {
             Ok(_) => (),
@@ -343,8 +650,80 @@ pub async fn fetch_daily_data(
     Ok(())
 }
 
+// Flattens a Chainflip swap request into the chainflip_swaps_detailed row shape
+pub fn format_chainflip_swap(node: &SwapNode) -> ChainflipSwapDetailed {
+    // Parse timestamp from completedBlockTimestamp or startedBlockTimestamp
+    let timestamp_string = match (
+        node.completedBlockTimestamp.as_ref(),
+        node.startedBlockTimestamp.as_ref(),
+    ) {
+        (Some(completed), _) => completed.clone(),
+        (_, Some(started)) => started.clone(),
+        _ => "1970-01-01T00:00:00Z".to_string(),
+    };
+
+    let dt = chrono::DateTime::parse_from_rfc3339(&timestamp_string)
+        .unwrap_or_else(|_| chrono::DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z").unwrap());
+
+    let record_timestamp = dt.timestamp();
+    let date = dt.format("%Y-%m-%d").to_string();
+
+    // Determine broker name
+    let broker_name = match &node.broker {
+        Some(broker) => broker.alias.clone(),
+        None => None,
+    };
+
+    ChainflipSwapDetailed {
+        timestamp: record_timestamp,
+        date,
+        swap_id: node.swapRequestNativeId.clone(),
+        source_asset: node.sourceAsset.to_uppercase(),
+        dest_asset: node.destAsset.to_uppercase(),
+        base_asset_leg1: node.baseAssetLeg1.clone().map(|a| a.to_uppercase()),
+        base_asset_leg2: node.baseAssetLeg2.clone().map(|a| a.to_uppercase()),
+        ingress_amount: node
+            .ingressAmount
+            .as_ref()
+            .and_then(|amount| parse_f64(amount).ok())
+            .unwrap_or(0.0),
+        ingress_value_usd: node
+            .ingressValueUsd
+            .as_ref()
+            .and_then(|amount| parse_f64(amount).ok())
+            .unwrap_or(0.0),
+        input_amount: node
+            .inputAmount
+            .as_ref()
+            .and_then(|amount| parse_f64(amount).ok())
+            .unwrap_or(0.0),
+        input_value_usd: node
+            .inputValueUsd
+            .as_ref()
+            .and_then(|amount| parse_f64(amount).ok())
+            .unwrap_or(0.0),
+        output_amount: node
+            .outputAmount
+            .as_ref()
+            .and_then(|amount| parse_f64(amount).ok())
+            .unwrap_or(0.0),
+        output_value_usd: node
+            .outputValueUsd
+            .as_ref()
+            .and_then(|amount| parse_f64(amount).ok())
+            .unwrap_or(0.0),
+        started_block_date: node.startedBlockDate.clone(),
+        started_block_id: node.startedBlockId,
+        started_block_timestamp: node.startedBlockTimestamp.clone(),
+        destination_address: node.destinationAddress.clone(),
+        refund_address: node.refundAddress.clone(),
+        status: node.status.clone(),
+        broker: broker_name,
+    }
+}
+
 pub async fn fetch_chainflip_swaps_incremental(
-    base_url: &str,
+    chainflip: &ChainFlip,
     pg: &PostgreSQL,
 ) -> Result<(), TransactionError> {
     println!("Starting incremental Chainflip swaps fetch");
@@ -367,7 +746,7 @@ pub async fn fetch_chainflip_swaps_incremental(
     println!("Latest timestamp in database: {}", latest_timestamp);
 
     let mut offset = 0;
-    let limit = 30;
+    let limit = chainflip.page_size();
     let mut total_fetched = 0;
     let mut total_inserted = 0;
     let mut total_skipped = 0;
@@ -375,13 +754,9 @@ pub async fn fetch_chainflip_swaps_incremental(
 
     'outer: loop {
         println!("Fetching batch: offset={}, limit={}", offset, limit);
-        let resp = match crate::utils::chainflip::ChainFlip::fetch_chainflip_swaps(
-            base_url,
-            Some(limit),
-            Some(offset),
-            None,
-        )
-        .await
+        let resp = match chainflip
+            .fetch_chainflip_swaps(Some(limit), Some(offset), None)
+            .await
         {
             Ok(response) => response,
             Err(err) => {
@@ -414,21 +789,8 @@ pub async fn fetch_chainflip_swaps_incremental(
                 continue;
             }
 
-            // Parse timestamp from completedBlockTimestamp or startedBlockTimestamp
-            let timestamp_string = match (
-                node.completedBlockTimestamp.as_ref(),
-                node.startedBlockTimestamp.as_ref(),
-            ) {
-                (Some(completed), _) => completed.clone(),
-                (_, Some(started)) => started.clone(),
-                _ => "1970-01-01T00:00:00Z".to_string(),
-            };
-
-            let dt = chrono::DateTime::parse_from_rfc3339(&timestamp_string).unwrap_or_else(|_| {
-                chrono::DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z").unwrap()
-            });
-
-            let record_timestamp = dt.timestamp();
+            let formatted_data = format_chainflip_swap(node);
+            let record_timestamp = formatted_data.timestamp;
 
             // Skip records that are older than or equal to our latest timestamp
             if record_timestamp <= latest_timestamp {
@@ -440,61 +802,6 @@ pub async fn fetch_chainflip_swaps_incremental(
                 break;
             }
 
-            let date = dt.format("%Y-%m-%d").to_string();
-
-            // Determine broker name
-            let broker_name = match &node.broker {
-                Some(broker) => broker.alias.clone(),
-                None => None,
-            };
-
-            let formatted_data = ChainflipSwapDetailed {
-                timestamp: record_timestamp,
-                date,
-                swap_id: node.swapRequestNativeId.clone(),
-                source_asset: node.sourceAsset.to_uppercase(),
-                dest_asset: node.destAsset.to_uppercase(),
-                base_asset_leg1: node.baseAssetLeg1.clone().map(|a| a.to_uppercase()),
-                base_asset_leg2: node.baseAssetLeg2.clone().map(|a| a.to_uppercase()),
-                ingress_amount: node
-                    .ingressAmount
-                    .as_ref()
-                    .and_then(|amount| parse_f64(amount).ok())
-                    .unwrap_or(0.0),
-                ingress_value_usd: node
-                    .ingressValueUsd
-                    .as_ref()
-                    .and_then(|amount| parse_f64(amount).ok())
-                    .unwrap_or(0.0),
-                input_amount: node
-                    .inputAmount
-                    .as_ref()
-                    .and_then(|amount| parse_f64(amount).ok())
-                    .unwrap_or(0.0),
-                input_value_usd: node
-                    .inputValueUsd
-                    .as_ref()
-                    .and_then(|amount| parse_f64(amount).ok())
-                    .unwrap_or(0.0),
-                output_amount: node
-                    .outputAmount
-                    .as_ref()
-                    .and_then(|amount| parse_f64(amount).ok())
-                    .unwrap_or(0.0),
-                output_value_usd: node
-                    .outputValueUsd
-                    .as_ref()
-                    .and_then(|amount| parse_f64(amount).ok())
-                    .unwrap_or(0.0),
-                started_block_date: node.startedBlockDate.clone(),
-                started_block_id: node.startedBlockId,
-                started_block_timestamp: node.startedBlockTimestamp.clone(),
-                destination_address: node.destinationAddress.clone(),
-                refund_address: node.refundAddress.clone(),
-                status: node.status.clone(),
-                broker: broker_name,
-            };
-
             match pg
                 .insert_chainflip_swap_detailed(formatted_data.clone())
                 .await
@@ -1,20 +1,33 @@
+mod cli;
+mod config;
 mod db;
 mod fetcher;
 mod models;
 mod routes;
 mod tests;
 mod utils;
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::sync::Arc;
 
 use actix_cors::Cors;
 use actix_web::{get, web::Data, App, HttpResponse, HttpServer, Responder};
-use db::PostgreSQL;
-use futures_util::lock::Mutex;
+use clap::Parser;
+use cli::{Cli, Command, ServeArgs};
+use config::{Config, SourcesConfig};
+use db::{PostgreSQL, SWAP_TABLE_COLUMNS};
 use lazy_static::lazy_static;
+use serde::Deserialize;
 use tokio::sync::Semaphore;
-use utils::cron::{
-    start_chainflip_swaps_incremental, start_cronjob, start_daily_fetch, start_fetch_closing_price,
-    start_retry,
+use utils::{
+    chainflip::ChainFlip,
+    coingecko::{CoinGecko, SharedCoinGecko},
+    cron::{
+        start_chainflip_swaps_incremental, start_cronjob, start_daily_fetch,
+        start_fetch_closing_price, start_price_candles, start_retry, start_swap_valuation,
+        start_volume_rollup,
+    },
+    midgard::MidGard,
+    pending_tracker::{PendingTracker, RetryPolicy},
+    price_provider::PriceProviderChain,
 };
 
 #[get("/")]
@@ -22,100 +35,272 @@ async fn home() -> impl Responder {
     HttpResponse::Ok().body("Rust Backend Server")
 }
 
-const NATIVE_SWAPS_BASE_URL: &str =
-    "https://vanaheimex.com/actions?asset=notrade,BTC.BTC&type=swap";
-const TRADE_SWAPS_BASE_URL: &str = "https://vanaheimex.com/actions?asset=trade,BTC~BTC&type=swap";
-const CHAINFLIP_BASE_URL: &str = "https://reporting-service.chainflip.io/graphql";
-
-lazy_static! {
-    pub static ref TRADE_SWAPS_PENDING_IDS: Arc<Mutex<HashSet<String>>> =
-        Arc::new(Mutex::new(HashSet::new()));
-}
 lazy_static! {
-    pub static ref NATIVE_SWAPS_PENDING_IDS: Arc<Mutex<HashSet<String>>> =
-        Arc::new(Mutex::new(HashSet::new()));
-}
-
-lazy_static::lazy_static! {
     static ref REQUEST_SEMAPHORE: Arc<Semaphore> = Arc::new(Semaphore::new(1));
-    static ref RATE_LIMIT_DELAY: Duration = Duration::from_millis(5000);
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Deserialize)]
 pub enum SwapType {
     NATIVE,
     TRADE,
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    let pg = PostgreSQL::init()
-        .await
-        .expect("Error Connecting to POSTGRESQL");
+impl SwapType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwapType::NATIVE => "NATIVE",
+            SwapType::TRADE => "TRADE",
+        }
+    }
+}
 
-    tokio::spawn({
-        let pg = pg.clone();
-        async move { start_cronjob(pg.clone(), NATIVE_SWAPS_BASE_URL, SwapType::NATIVE).await }
-    });
+// Upstream API clients built from the config
+#[derive(Clone)]
+struct Clients {
+    midgard: MidGard,
+    chainflip: ChainFlip,
+    coingecko: Option<SharedCoinGecko>,
+}
 
-    tokio::spawn({
-        let pg = pg.clone();
-        async move {
-            start_retry(
-                pg.clone(),
-                NATIVE_SWAPS_BASE_URL,
-                NATIVE_SWAPS_PENDING_IDS.clone(),
-                SwapType::NATIVE,
-            )
+impl Clients {
+    fn new(config: &Config) -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            midgard: MidGard::new(&config.midgard)?,
+            chainflip: ChainFlip::new(&config.chainflip)?,
+            coingecko: CoinGecko::shared(&config.coingecko),
+        })
+    }
+}
+
+// Fails fast on a source whose table is missing or predates the swap columns, instead of on
+// its first insert
+async fn check_source_tables(pg: &PostgreSQL, sources: &SourcesConfig) -> Result<(), String> {
+    for source in &sources.sources {
+        let missing = pg
+            .missing_swap_columns(&source.table)
             .await
+            .map_err(|err| format!("Error checking table {}: {}", source.table, err))?;
+        let problem = if missing.len() == SWAP_TABLE_COLUMNS.len() {
+            "does not exist".to_string()
+        } else if !missing.is_empty() {
+            format!("is missing columns {}", missing.join(", "))
+        } else {
+            continue;
+        };
+        return Err(format!(
+            "Table {} for source {} {}; create it with the native_swaps_thorchain DDL from \
+             migrations/",
+            source.table, source.name, problem
+        ));
+    }
+    Ok(())
+}
+
+// Starts the polling, retry, pricing and rollup jobs that run alongside the API
+fn spawn_workers(
+    pg: &PostgreSQL,
+    config: &Config,
+    clients: &Clients,
+    sources: &SourcesConfig,
+    prices: &PriceProviderChain,
+) {
+    let pending_tracker = PendingTracker::postgres(pg.clone());
+
+    for source in sources.sources.clone() {
+        tokio::spawn({
+            let pg = pg.clone();
+            let midgard = clients.midgard.clone();
+            let pending_tracker = pending_tracker.clone();
+            let source = source.clone();
+            async move { start_cronjob(pg, midgard, pending_tracker, source).await }
+        });
+
+        tokio::spawn({
+            let pg = pg.clone();
+            let midgard = clients.midgard.clone();
+            let pending_tracker = pending_tracker.clone();
+            let source = source.clone();
+            let retry_policy = RetryPolicy::new(&config.pending);
+            async move { start_retry(pg, midgard, pending_tracker, source, retry_policy).await }
+        });
+
+        if source.reconcile_daily {
+            tokio::spawn({
+                let pg = pg.clone();
+                let midgard = clients.midgard.clone();
+                let pending_tracker = pending_tracker.clone();
+                async move { start_daily_fetch(pg, midgard, pending_tracker, source).await }
+            });
         }
-    });
+    }
 
     tokio::spawn({
         let pg = pg.clone();
-        async move { start_cronjob(pg.clone(), TRADE_SWAPS_BASE_URL, SwapType::TRADE).await }
+        let prices = prices.clone();
+        let prices_config = config.prices.clone();
+        async move { start_fetch_closing_price(pg.clone(), prices, prices_config).await }
     });
 
-    tokio::spawn({
-        let pg = pg.clone();
-        async move {
-            start_retry(
-                pg.clone(),
-                TRADE_SWAPS_BASE_URL,
-                TRADE_SWAPS_PENDING_IDS.clone(),
-                SwapType::TRADE,
-            )
-            .await
-        }
-    });
+    if config.prices.candles_enabled {
+        tokio::spawn({
+            let pg = pg.clone();
+            let coingecko = clients.coingecko.clone();
+            let interval_secs = config.jobs.price_candles_interval_secs;
+            async move { start_price_candles(pg, coingecko, interval_secs).await }
+        });
+    }
 
     tokio::spawn({
         let pg = pg.clone();
-        async move { start_fetch_closing_price(pg.clone()).await }
+        let prices = prices.clone();
+        let sources = sources.sources.clone();
+        let interval_secs = config.jobs.valuation_interval_secs;
+        async move { start_swap_valuation(pg, prices, sources, interval_secs).await }
     });
 
     tokio::spawn({
         let pg = pg.clone();
-        async move { start_chainflip_swaps_incremental(pg.clone(), CHAINFLIP_BASE_URL).await }
+        let interval_secs = config.jobs.volume_rollup_interval_secs;
+        let window_days = config.jobs.volume_rollup_window_days;
+        async move { start_volume_rollup(pg, interval_secs, window_days).await }
     });
 
     tokio::spawn({
         let pg = pg.clone();
-        async move { start_daily_fetch(pg.clone()).await }
+        let chainflip = clients.chainflip.clone();
+        let interval_secs = config.chainflip.poll_interval_secs;
+        async move { start_chainflip_swaps_incremental(pg, chainflip, interval_secs).await }
     });
+}
+
+async fn serve(
+    pg: PostgreSQL,
+    config: &Config,
+    clients: Clients,
+    sources: SourcesConfig,
+    prices: PriceProviderChain,
+    args: &ServeArgs,
+) -> std::io::Result<()> {
+    if args.no_workers {
+        println!("Background Workers Disabled");
+    } else {
+        spawn_workers(&pg, config, &clients, &sources, &prices);
+    }
 
+    let bind = args.bind.as_deref().unwrap_or(&config.bind_address);
     let pg_data = Data::new(pg);
+    let midgard_data = Data::new(clients.midgard);
+    let chainflip_data = Data::new(clients.chainflip);
+    let sources_data = Data::new(sources);
+    let prices_data = Data::new(prices);
+    let prices_config_data = Data::new(config.prices.clone());
+    let admin_data = Data::new(config.admin.clone());
     let server = HttpServer::new(move || {
         App::new()
             .app_data(pg_data.clone())
+            .app_data(sources_data.clone())
+            .app_data(prices_data.clone())
+            .app_data(prices_config_data.clone())
+            .app_data(admin_data.clone())
+            .app_data(midgard_data.clone())
+            .app_data(chainflip_data.clone())
             .wrap(Cors::permissive())
             .service(home)
             .configure(routes::swap_history::init)
+            .configure(routes::pending_swaps::init)
+            .configure(routes::unified_swaps::init)
+            .configure(routes::chainflip_swaps::init)
+            .configure(routes::swap_lookup::init)
+            .configure(routes::addresses::init)
+            .configure(routes::stats::init)
+            .configure(routes::admin::init)
     })
-    .bind(("0.0.0.0", 3000))
-    .expect("Failed to bind Actix server")
+    .bind(bind)?
     .run();
 
-    server.await?;
+    println!("Listening on {}", bind);
+    server.await
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Error Loading Config: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let pg = match PostgreSQL::init(&config.database_url).await {
+        Ok(pg) => pg,
+        Err(err) => {
+            eprintln!("Error Connecting to POSTGRESQL: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let command = cli.command.unwrap_or_default();
+    let migrate_only = matches!(command, Command::Migrate);
+    if migrate_only || config.auto_migrate {
+        if let Err(err) = pg.migrate().await {
+            eprintln!("Error Applying Migrations: {}", err);
+            std::process::exit(1);
+        }
+    }
+    if migrate_only {
+        return Ok(());
+    }
+    let sources = match SourcesConfig::load() {
+        Ok(sources) => sources,
+        Err(err) => {
+            eprintln!("Error Loading Sources Config: {}", err);
+            std::process::exit(2);
+        }
+    };
+    if let Err(err) = check_source_tables(&pg, &sources).await {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    let clients = match Clients::new(&config) {
+        Ok(clients) => clients,
+        Err(err) => {
+            eprintln!("Error Building HTTP Clients: {}", err);
+            std::process::exit(1);
+        }
+    };
+    let prices = PriceProviderChain::from_config(
+        &config.prices,
+        &pg,
+        clients.coingecko.clone(),
+        &sources.sources,
+    );
+    println!("Price Providers : {}", prices.names().join(", "));
+
+    let result = match command {
+        Command::Serve(args) => return serve(pg, &config, clients, sources, prices, &args).await,
+        Command::Backfill(args) => cli::run_backfill(&pg, &clients.midgard, &sources, &args).await,
+        Command::Reconcile(args) => {
+            cli::run_reconcile(&pg, &clients.midgard, &sources, &args).await
+        }
+        Command::RetryPending(args) => {
+            cli::run_retry_pending(&pg, &clients.midgard, &sources, &config.pending, &args).await
+        }
+        Command::FetchPrices(args) => {
+            cli::run_fetch_prices(
+                &pg,
+                &prices,
+                clients.coingecko.as_ref(),
+                &config.prices,
+                &args,
+            )
+            .await
+        }
+        // Applied before the sources were loaded
+        Command::Migrate => Ok(()),
+    };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(err.exit_code());
+    }
     Ok(())
 }
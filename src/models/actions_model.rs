@@ -15,10 +15,36 @@ pub struct TransactionData {
     pub txID: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StreamingSwapMeta {
+    #[serde(default)]
+    pub count: Option<String>,
+    #[serde(default)]
+    pub quantity: Option<String>,
+    #[serde(default)]
+    pub interval: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TransactionMetaSwap {
     pub inPriceUSD: String,
     pub outPriceUSD: String,
+    #[serde(default)]
+    pub networkFees: Vec<SwapCoin>,
+    #[serde(default)]
+    pub liquidityFee: Option<String>,
+    #[serde(default)]
+    pub swapSlip: Option<String>,
+    #[serde(default)]
+    pub affiliateFee: Option<String>,
+    #[serde(default)]
+    pub affiliateAddress: Option<String>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub isStreamingSwap: Option<bool>,
+    #[serde(default)]
+    pub streamingSwapMeta: Option<StreamingSwapMeta>,
 }
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TransactionMetaData {
@@ -62,5 +88,34 @@ pub struct SwapTransactionFromatted {
     pub out_asset_2: Option<String>,
     pub out_amount_2: Option<f64>,
     pub out_address_2: Option<String>,
-    pub status : String
+    #[sqlx(default)]
+    pub status : String,
+    #[sqlx(default)]
+    pub in_amount_usd: Option<f64>,
+    #[sqlx(default)]
+    pub out_amount_usd: Option<f64>,
+    // Swap metadata, absent from tables that predate these columns
+    #[sqlx(default)]
+    pub network_fees: Option<String>,
+    #[sqlx(default)]
+    pub liquidity_fee: Option<f64>,
+    #[sqlx(default)]
+    pub swap_slip_bps: Option<i64>,
+    #[sqlx(default)]
+    pub affiliate_fee_bps: Option<i64>,
+    #[sqlx(default)]
+    pub affiliate_address: Option<String>,
+    #[sqlx(default)]
+    pub memo: Option<String>,
+    #[sqlx(default)]
+    pub is_streaming_swap: Option<bool>,
+    #[sqlx(default)]
+    pub streaming_quantity: Option<i64>,
+    #[sqlx(default)]
+    pub streaming_interval: Option<i64>,
+    // BTC closing price for the swap's date and the USD value of its BTC leg
+    #[sqlx(default)]
+    pub btc_price_usd: Option<f64>,
+    #[sqlx(default)]
+    pub btc_value_usd: Option<f64>,
 }
@@ -0,0 +1,27 @@
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct AddressActivity {
+    pub swap_count: i64,
+    pub total_in_usd: Option<f64>,
+    pub total_out_usd: Option<f64>,
+    pub first_activity: Option<i64>,
+    pub last_activity: Option<i64>,
+}
+
+// volume_in sums the asset where it was swapped from, volume_out where it was swapped to
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct AssetVolume {
+    pub asset: String,
+    pub volume_in: f64,
+    pub volume_out: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AddressSummary {
+    pub address: String,
+    #[serde(flatten)]
+    pub activity: AddressActivity,
+    pub assets: Vec<AssetVolume>,
+}
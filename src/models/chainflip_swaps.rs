@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SwapResponse {
@@ -97,7 +98,7 @@ pub struct ChainflipSwap {
     pub status: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct ChainflipSwapDetailed {
     pub timestamp: i64,
     pub date: String,
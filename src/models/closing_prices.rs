@@ -1,8 +1,31 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 
 #[derive(Serialize,Deserialize,Debug)]
 pub struct ClosingPriceInterval{
     pub date : String,
-    pub closing_price_usd : f64
-}
\ No newline at end of file
+    pub closing_price_usd : f64,
+    pub provider : String
+}
+
+// Row in closing_prices; `asset` is the symbol from utils::price_symbol and `provider` the
+// PriceProvider that supplied the price. coin_id is only set for CoinGecko prices.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AssetClosingPrice {
+    pub asset: String,
+    pub date: NaiveDate,
+    pub coin_id: Option<String>,
+    pub closing_price_usd: f64,
+    pub provider: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PriceCandle {
+    pub asset: String,
+    pub open_time: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
@@ -1,7 +1,14 @@
 use serde::{Deserialize, Serialize};
 pub mod actions_model;
-pub mod closing_prices;
+pub mod addresses;
 pub mod chainflip_swaps;
+pub mod closing_prices;
+pub mod pagination;
+pub mod pending_swaps;
+pub mod swap_lookup;
+pub mod sync_cursors;
+pub mod unified_swaps;
+pub mod volume_stats;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CurrentPrice {
@@ -21,6 +28,11 @@ pub struct PriceFetchResponse {
     pub market_data: MarketData,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MarketChartResponse {
+    pub prices: Vec<[f64; 2]>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CoinSearchData {
     pub id: String,
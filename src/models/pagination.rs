@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    pub page: u64,
+    pub limit: u64,
+    pub total: i64,
+    pub has_next: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Paginated<T> {
+    // `rows` is expected to hold up to `limit + 1` records; the extra one only signals a next page
+    pub fn from_rows(mut rows: Vec<T>, page: u64, limit: u64, total: i64) -> Self {
+        let has_next = rows.len() as u64 > limit;
+        rows.truncate(limit as usize);
+        Self {
+            data: rows,
+            page,
+            limit,
+            total,
+            has_next,
+            next_cursor: None,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PendingSwap {
+    pub tx_id: String,
+    pub source: String,
+    pub swap_type: String,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub attempt_count: i32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DeadLetterSwap {
+    pub tx_id: String,
+    pub source: String,
+    pub swap_type: String,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub attempt_count: i32,
+    pub last_error: Option<String>,
+    pub reason: String,
+    pub dead_lettered_at: DateTime<Utc>,
+}
@@ -0,0 +1,18 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LookupOrigin {
+    Cache,
+    Live,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SwapLookup<T> {
+    pub origin: LookupOrigin,
+    pub pending: bool,
+    // Name of the configured THORChain source the swap belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub data: T,
+}
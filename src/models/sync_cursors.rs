@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+// Backward syncs page into history with Midgard's nextPageToken, forward syncs follow new
+// actions with prevPageToken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncDirection {
+    Backward,
+    Forward,
+}
+
+impl SyncDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncDirection::Backward => "backward",
+            SyncDirection::Forward => "forward",
+        }
+    }
+}
+
+// Row in sync_cursors; `timestamp` is the swap timestamp the cursor has reached
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SyncCursor {
+    pub source: String,
+    pub direction: String,
+    pub token: Option<String>,
+    pub timestamp: Option<i64>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Thorchain,
+    Chainflip,
+}
+
+impl Protocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Protocol::Thorchain => "thorchain",
+            Protocol::Chainflip => "chainflip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct UnifiedSwap {
+    pub protocol: String,
+    pub swap_id: String,
+    pub source_asset: String,
+    pub dest_asset: String,
+    pub source_amount: f64,
+    pub dest_amount: f64,
+    pub source_amount_usd: Option<f64>,
+    pub dest_amount_usd: Option<f64>,
+    pub source_address: Option<String>,
+    pub dest_address: String,
+    pub timestamp: i64,
+    pub status: String,
+    // USD value of the swap's BTC leg
+    pub btc_value_usd: Option<f64>,
+}
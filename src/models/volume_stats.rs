@@ -0,0 +1,15 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct VolumeStat {
+    pub period_start: NaiveDate,
+    pub protocol: String,
+    pub swap_count: i64,
+    pub unique_addresses: i64,
+    pub volume_usd: Option<f64>,
+    pub btc_volume: f64,
+    pub btc_volume_usd: Option<f64>,
+    pub avg_size_usd: Option<f64>,
+}
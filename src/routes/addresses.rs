@@ -0,0 +1,88 @@
+use actix_web::{
+    get,
+    web::{self, ServiceConfig},
+    HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::PostgreSQL,
+    models::{addresses::AddressSummary, pagination::Paginated, unified_swaps::Protocol},
+    routes::{
+        errors::ErrorResponse,
+        pagination::{page_offset, validate_limit, validate_page, RawParam},
+    },
+};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AddressSwapsRequest {
+    protocol: Option<Protocol>,
+    page: Option<RawParam>,
+    limit: Option<RawParam>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AddressSummaryRequest {
+    protocol: Option<Protocol>,
+}
+
+// Matches THORChain in/out addresses and Chainflip refund/destination addresses
+#[get("/addresses/{address}/swaps")]
+pub async fn address_swaps(
+    pg: web::Data<PostgreSQL>,
+    path: web::Path<String>,
+    options: web::Query<AddressSwapsRequest>,
+) -> impl Responder {
+    let address = path.into_inner();
+    let options = options.into_inner();
+
+    let mut errors = Vec::new();
+    let page = validate_page(options.page.as_ref(), &mut errors);
+    let limit = validate_limit(options.limit.as_ref(), &mut errors);
+    if !errors.is_empty() {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::validation(errors));
+    }
+
+    let records = pg
+        .fetch_address_swaps(&address, options.protocol, limit, page_offset(page, limit))
+        .await;
+    let total = pg.count_address_swaps(&address, options.protocol).await;
+    match (records, total) {
+        (Ok(records), Ok(total)) => {
+            HttpResponse::Ok().json(Paginated::from_rows(records, page, limit, total))
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{:?}", err);
+            HttpResponse::InternalServerError().json(ErrorResponse::internal())
+        }
+    }
+}
+
+#[get("/addresses/{address}/summary")]
+pub async fn address_summary(
+    pg: web::Data<PostgreSQL>,
+    path: web::Path<String>,
+    options: web::Query<AddressSummaryRequest>,
+) -> impl Responder {
+    let address = path.into_inner();
+    let protocol = options.into_inner().protocol;
+
+    let activity = pg.fetch_address_activity(&address, protocol).await;
+    let assets = pg.fetch_address_asset_volumes(&address, protocol).await;
+    match (activity, assets) {
+        (Ok(activity), Ok(assets)) => HttpResponse::Ok().json(AddressSummary {
+            address,
+            activity,
+            assets,
+        }),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{:?}", err);
+            HttpResponse::InternalServerError().json(ErrorResponse::internal())
+        }
+    }
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(address_swaps);
+    config.service(address_summary);
+}
@@ -0,0 +1,113 @@
+use actix_web::{
+    post,
+    web::{self, ServiceConfig},
+    HttpRequest, HttpResponse, Responder,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{AdminConfig, PricesConfig},
+    db::PostgreSQL,
+    fetcher::{backfill_btc_closing_prices, last_closed_date},
+    routes::{
+        chainflip_swaps::validate_date,
+        errors::{ErrorResponse, FieldError},
+    },
+    utils::price_provider::PriceProviderChain,
+};
+
+// Admin routes are disabled unless admin.token is set and sent back in the x-admin-token header
+fn authorize(req: &HttpRequest, config: &AdminConfig) -> Result<(), HttpResponse> {
+    let expected = config.token();
+    let provided = req
+        .headers()
+        .get("x-admin-token")
+        .and_then(|value| value.to_str().ok());
+    match (expected, provided) {
+        (Some(expected), Some(provided)) if expected == provided => Ok(()),
+        _ => Err(HttpResponse::Forbidden().json(ErrorResponse::new(
+            "forbidden",
+            "missing or invalid admin token".to_string(),
+        ))),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BackfillRequest {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackfillRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl BackfillRequest {
+    // Defaults match the startup backfill: prices.backfill_start through the last closed day.
+    // Today has no close yet.
+    pub fn validate(
+        self,
+        backfill_start: NaiveDate,
+        last_closed: NaiveDate,
+    ) -> Result<BackfillRange, HttpResponse> {
+        let mut errors = Vec::new();
+        let from =
+            validate_date("from", self.from.as_deref(), &mut errors).unwrap_or(backfill_start);
+        let to = validate_date("to", self.to.as_deref(), &mut errors).unwrap_or(last_closed);
+        if to > last_closed {
+            errors.push(FieldError::new("to", "must be before today (UTC)"));
+        }
+        if from > to {
+            errors.push(FieldError::new("from", "must not be after to"));
+        }
+
+        if !errors.is_empty() {
+            return Err(HttpResponse::UnprocessableEntity().json(ErrorResponse::validation(errors)));
+        }
+        Ok(BackfillRange { from, to })
+    }
+}
+
+// Runs in the background since a long range takes several seconds per missing date
+#[post("/admin/closing-prices/backfill")]
+pub async fn backfill_closing_prices(
+    req: HttpRequest,
+    pg: web::Data<PostgreSQL>,
+    prices: web::Data<PriceProviderChain>,
+    admin: web::Data<AdminConfig>,
+    prices_config: web::Data<PricesConfig>,
+    body: Option<web::Json<BackfillRequest>>,
+) -> impl Responder {
+    if let Err(response) = authorize(&req, &admin) {
+        return response;
+    }
+    let body = body.map(|body| body.into_inner()).unwrap_or_default();
+    let range = match body.validate(prices_config.backfill_start(), last_closed_date()) {
+        Ok(range) => range,
+        Err(response) => return response,
+    };
+
+    tokio::spawn({
+        let pg = pg.get_ref().clone();
+        let prices = prices.get_ref().clone();
+        let range = range.clone();
+        async move {
+            match backfill_btc_closing_prices(&pg, &prices, range.from, range.to).await {
+                Ok(summary) => println!(
+                    "Closing Price Backfill Stored {} Prices, {} failed",
+                    summary.stored, summary.failed
+                ),
+                Err(e) => println!("Error backfilling closing prices: {}", e),
+            }
+        }
+    });
+
+    HttpResponse::Accepted().json(range)
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(backfill_closing_prices);
+}
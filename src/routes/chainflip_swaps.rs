@@ -0,0 +1,201 @@
+use actix_web::{
+    get,
+    web::{self, ServiceConfig},
+    HttpResponse, Responder,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::PostgreSQL,
+    models::pagination::Paginated,
+    routes::{
+        errors::{ErrorResponse, FieldError},
+        pagination::{page_offset, validate_limit, validate_page, RawParam},
+        swap_history::OrderType,
+    },
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainflipSortField {
+    Timestamp,
+    Date,
+    SwapId,
+    InputAmount,
+    InputValueUsd,
+    OutputAmount,
+    OutputValueUsd,
+}
+
+impl ChainflipSortField {
+    pub const ALL: [ChainflipSortField; 7] = [
+        ChainflipSortField::Timestamp,
+        ChainflipSortField::Date,
+        ChainflipSortField::SwapId,
+        ChainflipSortField::InputAmount,
+        ChainflipSortField::InputValueUsd,
+        ChainflipSortField::OutputAmount,
+        ChainflipSortField::OutputValueUsd,
+    ];
+
+    pub fn column(&self) -> &'static str {
+        match self {
+            ChainflipSortField::Timestamp => "timestamp",
+            ChainflipSortField::Date => "date",
+            ChainflipSortField::SwapId => "swap_id",
+            ChainflipSortField::InputAmount => "input_amount",
+            ChainflipSortField::InputValueUsd => "input_value_usd",
+            ChainflipSortField::OutputAmount => "output_amount",
+            ChainflipSortField::OutputValueUsd => "output_value_usd",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ChainflipSortField> {
+        Self::ALL.into_iter().find(|field| field.column() == value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ChainflipSwapsRequest {
+    sort_by: Option<String>,
+    order: Option<String>,
+    page: Option<RawParam>,
+    limit: Option<RawParam>,
+    source_asset: Option<String>,
+    dest_asset: Option<String>,
+    broker: Option<String>,
+    status: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    address: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainflipSwapsQuery {
+    pub sort_by: ChainflipSortField,
+    pub order: OrderType,
+    pub page: u64,
+    pub limit: u64,
+    pub source_asset: Option<String>,
+    pub dest_asset: Option<String>,
+    pub broker: Option<String>,
+    pub status: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub address: Option<String>,
+}
+
+impl ChainflipSwapsQuery {
+    pub fn offset(&self) -> i64 {
+        page_offset(self.page, self.limit)
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn validate_date(
+    field: &str,
+    value: Option<&str>,
+    errors: &mut Vec<FieldError>,
+) -> Option<NaiveDate> {
+    let value = value?;
+    match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => Some(date),
+        Err(_) => {
+            errors.push(FieldError::new(field, "must be an ISO date (YYYY-MM-DD)"));
+            None
+        }
+    }
+}
+
+impl ChainflipSwapsRequest {
+    pub fn validate(self) -> Result<ChainflipSwapsQuery, HttpResponse> {
+        let sort_by = match self.sort_by.as_deref() {
+            None => ChainflipSortField::Timestamp,
+            Some(value) => match ChainflipSortField::parse(value) {
+                Some(sort_by) => sort_by,
+                None => {
+                    let allowed: Vec<&str> = ChainflipSortField::ALL
+                        .iter()
+                        .map(|field| field.column())
+                        .collect();
+                    return Err(HttpResponse::BadRequest().json(ErrorResponse::new(
+                        "invalid_sort_by",
+                        format!("sort_by must be one of: {}", allowed.join(", ")),
+                    )));
+                }
+            },
+        };
+
+        let mut errors = Vec::new();
+        let page = validate_page(self.page.as_ref(), &mut errors);
+        let limit = validate_limit(self.limit.as_ref(), &mut errors);
+        let order = match self.order.as_deref() {
+            None => OrderType::DESC,
+            Some(value) => OrderType::parse(value).unwrap_or_else(|| {
+                errors.push(FieldError::new("order", "must be ASC or DESC"));
+                OrderType::DESC
+            }),
+        };
+        let from = validate_date("from", self.from.as_deref(), &mut errors);
+        let to = validate_date("to", self.to.as_deref(), &mut errors);
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                errors.push(FieldError::new("to", "must not be before from"));
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(HttpResponse::UnprocessableEntity().json(ErrorResponse::validation(errors)));
+        }
+
+        // Assets and statuses are upper-case in chainflip_swaps_detailed
+        Ok(ChainflipSwapsQuery {
+            sort_by,
+            order,
+            page,
+            limit,
+            source_asset: non_empty(self.source_asset).map(|asset| asset.to_uppercase()),
+            dest_asset: non_empty(self.dest_asset).map(|asset| asset.to_uppercase()),
+            broker: non_empty(self.broker),
+            status: non_empty(self.status).map(|status| status.to_uppercase()),
+            from,
+            to,
+            address: non_empty(self.address),
+        })
+    }
+}
+
+#[get("/chainflip/swaps")]
+pub async fn chainflip_swaps(
+    pg: web::Data<PostgreSQL>,
+    options: web::Query<ChainflipSwapsRequest>,
+) -> impl Responder {
+    let query = match options.into_inner().validate() {
+        Ok(query) => query,
+        Err(response) => return response,
+    };
+    let records = pg.fetch_chainflip_swaps(&query).await;
+    let total = pg.count_chainflip_swaps(&query).await;
+    match (records, total) {
+        (Ok(records), Ok(total)) => HttpResponse::Ok().json(Paginated::from_rows(
+            records,
+            query.page,
+            query.limit,
+            total,
+        )),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{:?}", err);
+            HttpResponse::InternalServerError().json(ErrorResponse::internal())
+        }
+    }
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(chainflip_swaps);
+}
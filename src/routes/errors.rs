@@ -0,0 +1,47 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl ErrorResponse {
+    pub fn new(error: &str, message: String) -> Self {
+        Self {
+            error: error.to_string(),
+            message,
+            fields: Vec::new(),
+        }
+    }
+
+    // Body for database failures; the underlying error is logged, not returned
+    pub fn internal() -> Self {
+        Self::new("internal_error", "Error fetching data".to_string())
+    }
+
+    pub fn validation(fields: Vec<FieldError>) -> Self {
+        Self {
+            error: "validation_failed".to_string(),
+            message: "Request parameters are invalid".to_string(),
+            fields,
+        }
+    }
+}
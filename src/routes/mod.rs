@@ -1 +1,10 @@
-pub mod swap_history;
\ No newline at end of file
+pub mod addresses;
+pub mod admin;
+pub mod chainflip_swaps;
+pub mod errors;
+pub mod pagination;
+pub mod pending_swaps;
+pub mod stats;
+pub mod swap_history;
+pub mod swap_lookup;
+pub mod unified_swaps;
\ No newline at end of file
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{routes::errors::FieldError, utils::parse_u64};
+
+pub const DEFAULT_LIMIT: u64 = 50;
+pub const MAX_LIMIT: u64 = 500;
+// Keeps the row offset well inside Postgres' bigint OFFSET
+pub const MAX_PAGE: u64 = 1_000_000;
+
+// Numeric parameters arrive as JSON numbers or strings in the POST body and as strings in the query
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum RawParam {
+    Number(serde_json::Number),
+    Text(String),
+}
+
+impl RawParam {
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            RawParam::Number(number) => number.as_u64(),
+            RawParam::Text(text) => parse_u64(text.trim()).ok(),
+        }
+    }
+}
+
+pub fn validate_page(page: Option<&RawParam>, errors: &mut Vec<FieldError>) -> u64 {
+    match page {
+        None => 1,
+        Some(raw) => match raw.as_u64() {
+            Some(page) if (1..=MAX_PAGE).contains(&page) => page,
+            _ => {
+                errors.push(FieldError::new(
+                    "page",
+                    &format!("must be an integer between 1 and {}", MAX_PAGE),
+                ));
+                1
+            }
+        },
+    }
+}
+
+pub fn validate_limit(limit: Option<&RawParam>, errors: &mut Vec<FieldError>) -> u64 {
+    match limit {
+        None => DEFAULT_LIMIT,
+        Some(raw) => match raw.as_u64() {
+            Some(limit) if (1..=MAX_LIMIT).contains(&limit) => limit,
+            _ => {
+                errors.push(FieldError::new(
+                    "limit",
+                    &format!("must be an integer between 1 and {}", MAX_LIMIT),
+                ));
+                DEFAULT_LIMIT
+            }
+        },
+    }
+}
+
+pub fn page_offset(page: u64, limit: u64) -> i64 {
+    i64::try_from((page - 1).saturating_mul(limit)).unwrap_or(i64::MAX)
+}
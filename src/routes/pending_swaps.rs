@@ -0,0 +1,31 @@
+use actix_web::{
+    get,
+    web::{self, ServiceConfig},
+    HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{db::PostgreSQL, routes::errors::ErrorResponse};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeadLetterQuery {
+    source: Option<String>,
+}
+
+#[get("/pending-swaps/dead-letter")]
+pub async fn dead_letter_swaps(
+    pg: web::Data<PostgreSQL>,
+    query: web::Query<DeadLetterQuery>,
+) -> impl Responder {
+    match pg.fetch_dead_letter_swaps(query.into_inner().source).await {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::InternalServerError().json(ErrorResponse::internal())
+        }
+    }
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(dead_letter_swaps);
+}
@@ -0,0 +1,117 @@
+use actix_web::{
+    get,
+    web::{self, ServiceConfig},
+    HttpResponse, Responder,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::PostgreSQL,
+    models::unified_swaps::Protocol,
+    routes::{
+        chainflip_swaps::validate_date,
+        errors::{ErrorResponse, FieldError},
+    },
+};
+
+const DEFAULT_RANGE_DAYS: i64 = 90;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsInterval {
+    Day,
+    Week,
+    Month,
+}
+
+impl StatsInterval {
+    // Also used as the DATE_TRUNC unit
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsInterval::Day => "day",
+            StatsInterval::Week => "week",
+            StatsInterval::Month => "month",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<StatsInterval> {
+        [
+            StatsInterval::Day,
+            StatsInterval::Week,
+            StatsInterval::Month,
+        ]
+        .into_iter()
+        .find(|interval| interval.as_str() == value.to_lowercase())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct VolumeStatsRequest {
+    interval: Option<String>,
+    from: Option<String>,
+    to: Option<String>,
+    protocol: Option<Protocol>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeStatsQuery {
+    pub interval: StatsInterval,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub protocol: Option<Protocol>,
+}
+
+impl VolumeStatsRequest {
+    // `to` defaults to today (UTC) and `from` to DEFAULT_RANGE_DAYS before `to`
+    pub fn validate(self) -> Result<VolumeStatsQuery, HttpResponse> {
+        let mut errors = Vec::new();
+        let interval = match self.interval.as_deref() {
+            None => StatsInterval::Day,
+            Some(value) => StatsInterval::parse(value).unwrap_or_else(|| {
+                errors.push(FieldError::new("interval", "must be day, week or month"));
+                StatsInterval::Day
+            }),
+        };
+        let to = validate_date("to", self.to.as_deref(), &mut errors)
+            .unwrap_or_else(|| Utc::now().date_naive());
+        let from = validate_date("from", self.from.as_deref(), &mut errors)
+            .unwrap_or_else(|| to - Duration::days(DEFAULT_RANGE_DAYS));
+        if from > to {
+            errors.push(FieldError::new("to", "must not be before from"));
+        }
+
+        if !errors.is_empty() {
+            return Err(HttpResponse::UnprocessableEntity().json(ErrorResponse::validation(errors)));
+        }
+
+        Ok(VolumeStatsQuery {
+            interval,
+            from,
+            to,
+            protocol: self.protocol,
+        })
+    }
+}
+
+#[get("/stats/volume")]
+pub async fn volume_stats(
+    pg: web::Data<PostgreSQL>,
+    options: web::Query<VolumeStatsRequest>,
+) -> impl Responder {
+    let query = match options.into_inner().validate() {
+        Ok(query) => query,
+        Err(response) => return response,
+    };
+    match pg.fetch_volume_stats(&query).await {
+        Ok(result) => HttpResponse::Ok().json(result),
+        Err(err) => {
+            println!("{:?}", err);
+            HttpResponse::InternalServerError().json(ErrorResponse::internal())
+        }
+    }
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(volume_stats);
+}
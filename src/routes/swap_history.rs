@@ -1,61 +1,255 @@
 use actix_web::{
-    post,
+    get, post,
     web::{self, ServiceConfig},
     HttpResponse, Responder,
 };
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-use crate::{db::PostgreSQL, utils::parse_u64};
+use crate::{
+    db::{PostgreSQL, SWAP_HISTORY_TABLE},
+    models::pagination::Paginated,
+    routes::{
+        errors::{ErrorResponse, FieldError},
+        pagination::{page_offset, validate_limit, validate_page, RawParam},
+    },
+};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub enum OrderType {
     ASC,
     DESC,
 }
-#[derive(Serialize, Deserialize, Debug)]
+
+impl OrderType {
+    pub fn parse(value: &str) -> Option<OrderType> {
+        match value.to_uppercase().as_str() {
+            "ASC" => Some(OrderType::ASC),
+            "DESC" => Some(OrderType::DESC),
+            _ => None,
+        }
+    }
+}
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    Timestamp,
+    Date,
+    TxId,
+    InAsset,
+    InAmount,
+    OutAsset1,
+    OutAmount1,
+    OutAsset2,
+    OutAmount2,
+}
+
+impl SortField {
+    pub const ALL: [SortField; 9] = [
+        SortField::Timestamp,
+        SortField::Date,
+        SortField::TxId,
+        SortField::InAsset,
+        SortField::InAmount,
+        SortField::OutAsset1,
+        SortField::OutAmount1,
+        SortField::OutAsset2,
+        SortField::OutAmount2,
+    ];
+
+    pub fn column(&self) -> &'static str {
+        match self {
+            SortField::Timestamp => "timestamp",
+            SortField::Date => "date",
+            SortField::TxId => "tx_id",
+            SortField::InAsset => "in_asset",
+            SortField::InAmount => "in_amount",
+            SortField::OutAsset1 => "out_asset_1",
+            SortField::OutAmount1 => "out_amount_1",
+            SortField::OutAsset2 => "out_asset_2",
+            SortField::OutAmount2 => "out_amount_2",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<SortField> {
+        Self::ALL.into_iter().find(|field| field.column() == value)
+    }
+}
+
+// Keyset position after the last row of a page, encoded as `<timestamp>:<tx_id>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapCursor {
+    pub timestamp: i64,
+    pub tx_id: String,
+}
+
+impl SwapCursor {
+    pub fn parse(value: &str) -> Option<SwapCursor> {
+        let (timestamp, tx_id) = value.split_once(':')?;
+        if tx_id.is_empty() {
+            return None;
+        }
+        Some(SwapCursor {
+            timestamp: timestamp.parse::<i64>().ok()?,
+            tx_id: tx_id.to_string(),
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.timestamp, self.tx_id)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct RequestBody {
-    sort_by: String,
-    page: String,
-    limit: String,
-    order: String,
+    sort_by: Option<String>,
+    page: Option<RawParam>,
+    limit: Option<RawParam>,
+    order: Option<String>,
     search: Option<String>,
     date: Option<String>,
+    cursor: Option<String>,
 }
-#[post("/swaps")]
-pub async fn swap_history(
-    pg: web::Data<PostgreSQL>,
-    options: web::Json<RequestBody>,
-) -> impl Responder {
-    let options = options.into_inner();
-    let order;
-    if options.order == "ASC" {
-        order = OrderType::ASC;
-    } else {
-        order = OrderType::DESC;
-    }
-    let page = parse_u64(&options.page).unwrap();
-    let limit = parse_u64(&options.limit).unwrap();
-    let offset: u64 = (page - 1) * limit;
-    let records = pg
-        .fetch_all(
-            "btc_user_data",
-            order,
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapHistoryQuery {
+    pub sort_by: SortField,
+    pub page: u64,
+    pub limit: u64,
+    pub order: OrderType,
+    pub search: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub cursor: Option<SwapCursor>,
+}
+
+impl SwapHistoryQuery {
+    pub fn offset(&self) -> i64 {
+        page_offset(self.page, self.limit)
+    }
+}
+
+impl RequestBody {
+    // Unknown sort fields are rejected on their own (400) so they can never reach the ORDER BY
+    pub fn validate(self) -> Result<SwapHistoryQuery, HttpResponse> {
+        let sort_by = match self.sort_by.as_deref() {
+            None => SortField::Timestamp,
+            Some(value) => match SortField::parse(value) {
+                Some(sort_by) => sort_by,
+                None => {
+                    let allowed: Vec<&str> =
+                        SortField::ALL.iter().map(|field| field.column()).collect();
+                    return Err(HttpResponse::BadRequest().json(ErrorResponse::new(
+                        "invalid_sort_by",
+                        format!("sort_by must be one of: {}", allowed.join(", ")),
+                    )));
+                }
+            },
+        };
+
+        let mut errors = Vec::new();
+
+        let page = validate_page(self.page.as_ref(), &mut errors);
+        let limit = validate_limit(self.limit.as_ref(), &mut errors);
+
+        let order = match self.order.as_deref() {
+            None => OrderType::DESC,
+            Some(value) => OrderType::parse(value).unwrap_or_else(|| {
+                errors.push(FieldError::new("order", "must be ASC or DESC"));
+                OrderType::DESC
+            }),
+        };
+
+        let date = match self.date.as_deref() {
+            None => None,
+            Some(value) => match NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(_) => {
+                    errors.push(FieldError::new("date", "must be an ISO date (YYYY-MM-DD)"));
+                    None
+                }
+            },
+        };
+
+        let cursor = match self.cursor.as_deref() {
+            None => None,
+            Some(value) => match SwapCursor::parse(value) {
+                Some(_) if sort_by != SortField::Timestamp => {
+                    errors.push(FieldError::new(
+                        "cursor",
+                        "is only supported when sorting by timestamp",
+                    ));
+                    None
+                }
+                Some(cursor) => Some(cursor),
+                None => {
+                    errors.push(FieldError::new("cursor", "must be <timestamp>:<tx_id>"));
+                    None
+                }
+            },
+        };
+
+        let search = self.search.filter(|search| !search.trim().is_empty());
+
+        if !errors.is_empty() {
+            return Err(HttpResponse::UnprocessableEntity().json(ErrorResponse::validation(errors)));
+        }
+
+        Ok(SwapHistoryQuery {
+            sort_by,
+            page,
             limit,
-            options.sort_by,
-            offset,
-            options.search,
-            options.date,
-        )
-        .await;
-    match records {
-        Ok(result) => HttpResponse::Ok().json(result),
-        Err(err) => {
+            order,
+            search,
+            date,
+            cursor,
+        })
+    }
+}
+
+async fn fetch_swap_history(pg: &PostgreSQL, options: RequestBody) -> HttpResponse {
+    let query = match options.validate() {
+        Ok(query) => query,
+        Err(response) => return response,
+    };
+    let records = pg.fetch_all(SWAP_HISTORY_TABLE, &query).await;
+    let total = pg.count_all(SWAP_HISTORY_TABLE, &query).await;
+    match (records, total) {
+        (Ok(records), Ok(total)) => {
+            let mut result = Paginated::from_rows(records, query.page, query.limit, total);
+            if result.has_next && query.sort_by == SortField::Timestamp {
+                result.next_cursor = result.data.last().map(|last| {
+                    SwapCursor {
+                        timestamp: last.timestamp,
+                        tx_id: last.tx_id.clone(),
+                    }
+                    .encode()
+                });
+            }
+            HttpResponse::Ok().json(result)
+        }
+        (Err(err), _) | (_, Err(err)) => {
             println!("{:?}", err);
             HttpResponse::BadRequest().json("Error Fetching Data")
         }
     }
 }
 
+#[post("/swaps")]
+pub async fn swap_history(
+    pg: web::Data<PostgreSQL>,
+    options: web::Json<RequestBody>,
+) -> impl Responder {
+    fetch_swap_history(&pg, options.into_inner()).await
+}
+
+#[get("/swaps")]
+pub async fn swap_history_query(
+    pg: web::Data<PostgreSQL>,
+    options: web::Query<RequestBody>,
+) -> impl Responder {
+    fetch_swap_history(&pg, options.into_inner()).await
+}
+
 pub fn init(config: &mut ServiceConfig) {
-    config.service(swap_history);
+    config.service(swap_history).service(swap_history_query);
 }
@@ -0,0 +1,191 @@
+use actix_web::{
+    get,
+    web::{self, ServiceConfig},
+    HttpResponse, Responder,
+};
+use sqlx::Error as SqlxError;
+
+use crate::{
+    config::SourcesConfig,
+    db::PostgreSQL,
+    fetcher::format_chainflip_swap,
+    models::swap_lookup::{LookupOrigin, SwapLookup},
+    routes::errors::ErrorResponse,
+    utils::{
+        chainflip::ChainFlip, midgard::MidGard, pending_tracker::PendingTracker,
+        transaction_handler::TransactionHandler,
+    },
+};
+
+fn not_found(message: String) -> HttpResponse {
+    HttpResponse::NotFound().json(ErrorResponse::new("not_found", message))
+}
+
+fn upstream_error(message: String) -> HttpResponse {
+    HttpResponse::BadGateway().json(ErrorResponse::new("upstream_error", message))
+}
+
+fn internal_error(err: SqlxError) -> HttpResponse {
+    println!("{:?}", err);
+    HttpResponse::InternalServerError().json(ErrorResponse::internal())
+}
+
+// THORChain tx ids are 32-byte hashes written as hex; Midgard reports them in upper case
+fn normalize_tx_id(tx_id: &str) -> Option<String> {
+    if tx_id.len() == 64 && tx_id.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(tx_id.to_ascii_uppercase())
+    } else {
+        None
+    }
+}
+
+// Checks every configured THORChain table and the swap history before asking Midgard
+#[get("/swaps/{tx_id}")]
+pub async fn thorchain_swap(
+    pg: web::Data<PostgreSQL>,
+    midgard: web::Data<MidGard>,
+    sources: web::Data<SourcesConfig>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let tx_id = match normalize_tx_id(&path.into_inner()) {
+        Some(tx_id) => tx_id,
+        None => {
+            return HttpResponse::UnprocessableEntity().json(ErrorResponse::new(
+                "invalid_tx_id",
+                "tx_id must be a 64 character hex transaction hash".to_string(),
+            ))
+        }
+    };
+
+    for source in &sources.sources {
+        match pg
+            .fetch_swap_by_tx_id(&source.table, &source.name, &tx_id)
+            .await
+        {
+            Ok(Some(swap)) => {
+                return HttpResponse::Ok().json(SwapLookup {
+                    origin: LookupOrigin::Cache,
+                    pending: swap.status != "success",
+                    source: Some(source.name.clone()),
+                    data: swap,
+                })
+            }
+            Ok(None) => {}
+            Err(err) => return internal_error(err),
+        }
+    }
+    match pg.fetch_swap_history_by_tx_id(&tx_id).await {
+        Ok(Some(swap)) => {
+            return HttpResponse::Ok().json(SwapLookup {
+                origin: LookupOrigin::Cache,
+                pending: false,
+                source: None,
+                data: swap,
+            })
+        }
+        Ok(None) => {}
+        Err(err) => return internal_error(err),
+    }
+
+    let transaction_handler =
+        TransactionHandler::new(PendingTracker::postgres(pg.get_ref().clone()));
+    let mut failed_sources = Vec::new();
+    for source in &sources.sources {
+        let resp = match midgard
+            .lookup_action_with_transactionid(&source.base_url(), &tx_id)
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                println!(
+                    "Error fetching transaction {} from {}: {:?}",
+                    tx_id, source.name, err
+                );
+                failed_sources.push(source.name.as_str());
+                continue;
+            }
+        };
+        // Midgard matches txid against every leg of an action, so only accept the swap it started
+        let swap = resp.actions.iter().find(|action| {
+            action
+                .in_data
+                .first()
+                .and_then(|leg| leg.txID.as_deref())
+                .is_some_and(|id| id.eq_ignore_ascii_case(&tx_id))
+        });
+        let swap = match swap {
+            Some(swap) => swap,
+            None => continue,
+        };
+        return match transaction_handler.parse_transaction(swap).await {
+            Ok(swap) => HttpResponse::Ok().json(SwapLookup {
+                origin: LookupOrigin::Live,
+                pending: swap.status != "success",
+                source: Some(source.name.clone()),
+                data: swap,
+            }),
+            Err(err) => {
+                println!("Error parsing transaction {}: {:?}", tx_id, err);
+                upstream_error(format!("Error parsing transaction {}: {}", tx_id, err))
+            }
+        };
+    }
+
+    // A miss is only trusted when at least one source answered
+    if !failed_sources.is_empty() && failed_sources.len() == sources.sources.len() {
+        return upstream_error(format!(
+            "Error fetching transaction {} from Midgard ({})",
+            tx_id,
+            failed_sources.join(", ")
+        ));
+    }
+    not_found(format!("No swap found for tx_id {}", tx_id))
+}
+
+#[get("/chainflip/swaps/{swap_id}")]
+pub async fn chainflip_swap(
+    pg: web::Data<PostgreSQL>,
+    chainflip: web::Data<ChainFlip>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let swap_id = path.into_inner();
+    if swap_id.parse::<u64>().is_err() {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::new(
+            "invalid_swap_id",
+            "swap_id must be a numeric swap request id".to_string(),
+        ));
+    }
+
+    match pg.fetch_chainflip_swap_by_id(&swap_id).await {
+        Ok(Some(swap)) => {
+            return HttpResponse::Ok().json(SwapLookup {
+                origin: LookupOrigin::Cache,
+                pending: swap.status != "SUCCESS",
+                source: None,
+                data: swap,
+            })
+        }
+        Ok(None) => {}
+        Err(err) => return internal_error(err),
+    }
+
+    match chainflip.fetch_chainflip_swap_by_id(&swap_id).await {
+        Ok(Some(node)) => HttpResponse::Ok().json(SwapLookup {
+            origin: LookupOrigin::Live,
+            pending: node.isInProgress,
+            source: None,
+            data: format_chainflip_swap(&node),
+        }),
+        Ok(None) => not_found(format!("No Chainflip swap found for swap_id {}", swap_id)),
+        Err(err) => {
+            println!("Error fetching Chainflip swap {}: {:?}", swap_id, err);
+            upstream_error(format!("Error fetching Chainflip swap {}", swap_id))
+        }
+    }
+}
+
+// Must be configured after unified_swaps so /swaps/unified is not captured as a tx_id
+pub fn init(config: &mut ServiceConfig) {
+    config.service(thorchain_swap);
+    config.service(chainflip_swap);
+}
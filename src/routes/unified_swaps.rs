@@ -0,0 +1,55 @@
+use actix_web::{
+    get,
+    web::{self, ServiceConfig},
+    HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::PostgreSQL,
+    models::{pagination::Paginated, unified_swaps::Protocol},
+    routes::{
+        errors::ErrorResponse,
+        pagination::{page_offset, validate_limit, validate_page, RawParam},
+    },
+};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct UnifiedSwapsQuery {
+    protocol: Option<Protocol>,
+    page: Option<RawParam>,
+    limit: Option<RawParam>,
+}
+
+#[get("/swaps/unified")]
+pub async fn unified_swaps(
+    pg: web::Data<PostgreSQL>,
+    query: web::Query<UnifiedSwapsQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+
+    let mut errors = Vec::new();
+    let page = validate_page(query.page.as_ref(), &mut errors);
+    let limit = validate_limit(query.limit.as_ref(), &mut errors);
+    if !errors.is_empty() {
+        return HttpResponse::UnprocessableEntity().json(ErrorResponse::validation(errors));
+    }
+
+    let records = pg
+        .fetch_unified_swaps(query.protocol, limit, page_offset(page, limit))
+        .await;
+    let total = pg.count_unified_swaps(query.protocol).await;
+    match (records, total) {
+        (Ok(records), Ok(total)) => {
+            HttpResponse::Ok().json(Paginated::from_rows(records, page, limit, total))
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            println!("{:?}", err);
+            HttpResponse::InternalServerError().json(ErrorResponse::internal())
+        }
+    }
+}
+
+pub fn init(config: &mut ServiceConfig) {
+    config.service(unified_swaps);
+}
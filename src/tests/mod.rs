@@ -1,11 +1,37 @@
+#[cfg(test)]
+mod routes;
+
 #[cfg(test)]
 mod tests {
+    use crate::cli::{select_sources, Cli, CliError, Command};
+    use crate::config::{Config, SourceConfig, SourcesConfig, DEFAULT_BIND_ADDRESS};
+    use crate::db::{
+        InsertSummary, PostgreSQL, INSERT_CHUNK_SIZE, SWAP_HISTORY_TABLE, SWAP_TABLE_COLUMNS,
+    };
+    use crate::models::{
+        actions_model::SwapTransaction,
+        pending_swaps::{DeadLetterSwap, PendingSwap},
+        sync_cursors::SyncDirection,
+    };
+    use crate::routes::swap_history::RequestBody;
     use crate::utils::{
-        convert_nano_to_sec, convert_to_standard_unit, format_date_for_sql, parse_f64, parse_u64,
-        read_next_page_token_from_file, write_next_page_token_to_file,
+        convert_nano_to_sec, convert_to_standard_unit, format_date_for_sql, hourly_candles,
+        parse_f64, parse_u64,
+        pending_tracker::{PendingStore, PendingTracker, RetryPolicy},
+        price_provider::{
+            latest_leg_price, PriceError, PriceProvider, PriceProviderChain, ProvidedPrice,
+        },
+        price_symbol,
+        transaction_handler::TransactionHandler,
     };
+    use crate::SwapType;
 
-    use std::fs;
+    use async_trait::async_trait;
+    use chrono::{Duration, NaiveDate, Utc};
+    use clap::Parser;
+    use sqlx::Error as SqlxError;
+    use std::{collections::HashMap, sync::Arc};
+    use tokio::sync::Mutex;
 
     #[test]
     fn test_convert_to_standard_unit() {
@@ -25,6 +51,124 @@ mod tests {
         assert!(parse_f64("abc").is_err());
     }
 
+    #[test]
+    fn test_price_symbol() {
+        assert_eq!(price_symbol("BTC.BTC").as_deref(), Some("BTC"));
+        assert_eq!(price_symbol("BTC~BTC").as_deref(), Some("BTC"));
+        assert_eq!(
+            price_symbol("ETH.USDC-0XA0B86991C6218B36C1D19D4A2E9EB0CE3606EB48").as_deref(),
+            Some("USDC")
+        );
+        assert_eq!(price_symbol("ETH").as_deref(), Some("ETH"));
+        assert_eq!(price_symbol("ArbUsdc").as_deref(), Some("USDC"));
+        assert_eq!(price_symbol("SOL").as_deref(), Some("SOL"));
+        assert_eq!(price_symbol(""), None);
+    }
+
+    #[test]
+    fn test_hourly_candles() {
+        // 2025-01-01T00:00:00Z, two points in the first hour and one in the next, out of order
+        let points = vec![
+            (1_735_691_400_000, 101.0),
+            (1_735_689_600_000, 100.0),
+            (1_735_693_200_000, 99.0),
+            (1_735_690_500_000, 104.0),
+        ];
+        let candles = hourly_candles("BTC", &points);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].open_time.timestamp(), 1_735_689_600);
+        assert_eq!(
+            (
+                candles[0].open,
+                candles[0].high,
+                candles[0].low,
+                candles[0].close
+            ),
+            (100.0, 104.0, 100.0, 101.0)
+        );
+        assert_eq!(candles[1].open_time.timestamp(), 1_735_693_200);
+        assert_eq!(candles[1].close, 99.0);
+    }
+
+    #[test]
+    fn test_latest_leg_price() {
+        let legs = vec![
+            (
+                "ETH.USDC-0XA0B86991C6218B36C1D19D4A2E9EB0CE3606EB48".to_string(),
+                0.999,
+                200,
+            ),
+            ("BTC.BTC".to_string(), 97000.0, 100),
+            ("ARBUSDC".to_string(), 1.001, 300),
+            ("BTC~BTC".to_string(), 98000.0, 50),
+        ];
+        assert_eq!(latest_leg_price("USDC", &legs), Some((1.001, 300)));
+        assert_eq!(latest_leg_price("BTC", &legs), Some((97000.0, 100)));
+        assert_eq!(latest_leg_price("ETH", &legs), None);
+    }
+
+    // Fails for every symbol except `symbol`
+    struct StubProvider {
+        name: &'static str,
+        symbol: &'static str,
+        price_usd: f64,
+    }
+
+    #[async_trait]
+    impl PriceProvider for StubProvider {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        async fn closing_price(
+            &self,
+            symbol: &str,
+            date: NaiveDate,
+        ) -> Result<ProvidedPrice, PriceError> {
+            if symbol != self.symbol {
+                return Err(PriceError::NotFound {
+                    provider: self.name,
+                    symbol: symbol.to_string(),
+                    date,
+                });
+            }
+            Ok(ProvidedPrice {
+                price_usd: self.price_usd,
+                provider: self.name,
+                coin_id: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_price_provider_chain_falls_back() {
+        let chain = PriceProviderChain::new(vec![
+            Arc::new(StubProvider {
+                name: "coingecko",
+                symbol: "BTC",
+                price_usd: 97000.0,
+            }),
+            Arc::new(StubProvider {
+                name: "midgard",
+                symbol: "RUNE",
+                price_usd: 1.5,
+            }),
+        ]);
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+
+        let btc = chain.closing_price("BTC", date).await.unwrap();
+        assert_eq!((btc.price_usd, btc.provider), (97000.0, "coingecko"));
+
+        let rune = chain.closing_price("RUNE", date).await.unwrap();
+        assert_eq!((rune.price_usd, rune.provider), (1.5, "midgard"));
+
+        assert!(matches!(
+            chain.closing_price("FLIP", date).await,
+            Err(PriceError::Exhausted { .. })
+        ));
+        assert_eq!(chain.names(), vec!["coingecko", "midgard"]);
+    }
+
     #[test]
     fn test_parse_u64() {
         assert_eq!(parse_u64("123456").unwrap(), 123456);
@@ -37,21 +181,946 @@ mod tests {
         assert!(format_date_for_sql("invalid-date").is_err());
     }
 
-    const TOKEN_FILE_PATH: &str = "next_page_token_test.txt";
+    #[test]
+    fn test_backfill_args() {
+        let cli = Cli::try_parse_from([
+            "swap-data-fetcher",
+            "backfill",
+            "--source",
+            "btc-native",
+            "--from",
+            "1700000000",
+            "--to",
+            "1710000000",
+            "--batch-size",
+            "5",
+        ])
+        .unwrap();
+        let Some(Command::Backfill(args)) = cli.command else {
+            panic!("expected backfill subcommand");
+        };
+        assert_eq!(args.source, "btc-native");
+        let options = args.options().unwrap();
+        assert_eq!(options.from, Some(1_700_000_000));
+        assert_eq!(options.to, Some(1_710_000_000));
+        assert_eq!(options.batch_size, 5);
+        assert!(!options.restart);
+
+        let cli = Cli::try_parse_from(["swap-data-fetcher", "backfill", "--source", "btc-native"])
+            .unwrap();
+        let Some(Command::Backfill(args)) = cli.command else {
+            panic!("expected backfill subcommand");
+        };
+        assert_eq!(args.options().unwrap().batch_size, 20);
+
+        let cli = Cli::try_parse_from([
+            "swap-data-fetcher",
+            "backfill",
+            "--source",
+            "btc-native",
+            "--from",
+            "200",
+            "--to",
+            "100",
+        ])
+        .unwrap();
+        let Some(Command::Backfill(args)) = cli.command else {
+            panic!("expected backfill subcommand");
+        };
+        assert!(args.options().is_err());
+
+        assert!(Cli::try_parse_from(["swap-data-fetcher", "backfill"]).is_err());
+        assert!(Cli::try_parse_from([
+            "swap-data-fetcher",
+            "backfill",
+            "--source",
+            "btc-native",
+            "--batch-size",
+            "0"
+        ])
+        .is_err());
+        assert!(Cli::try_parse_from(["swap-data-fetcher"])
+            .unwrap()
+            .command
+            .is_none());
+    }
+
+    #[test]
+    fn test_cli_subcommands() {
+        let Command::Serve(serve) = Cli::try_parse_from(["swap-data-fetcher"])
+            .unwrap()
+            .command
+            .unwrap_or_default()
+        else {
+            panic!("expected serve by default");
+        };
+        assert!(!serve.no_workers);
+        assert_eq!(serve.bind, None);
+
+        let cli = Cli::try_parse_from([
+            "swap-data-fetcher",
+            "serve",
+            "--no-workers",
+            "--bind",
+            "127.0.0.1:8080",
+        ])
+        .unwrap();
+        let Some(Command::Serve(serve)) = cli.command else {
+            panic!("expected serve subcommand");
+        };
+        assert!(serve.no_workers);
+        assert_eq!(serve.bind.as_deref(), Some("127.0.0.1:8080"));
+
+        let cli = Cli::try_parse_from([
+            "swap-data-fetcher",
+            "reconcile",
+            "--date",
+            "2025-01-01",
+            "--source",
+            "btc-trade",
+        ])
+        .unwrap();
+        let Some(Command::Reconcile(reconcile)) = cli.command else {
+            panic!("expected reconcile subcommand");
+        };
+        assert_eq!(reconcile.date, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        assert_eq!(reconcile.sources.source.as_deref(), Some("btc-trade"));
+        assert!(
+            Cli::try_parse_from(["swap-data-fetcher", "reconcile", "--date", "01-01-2025"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from(["swap-data-fetcher", "reconcile"]).is_err());
+
+        let cli = Cli::try_parse_from(["swap-data-fetcher", "retry-pending"]).unwrap();
+        let Some(Command::RetryPending(retry)) = cli.command else {
+            panic!("expected retry-pending subcommand");
+        };
+        assert_eq!(retry.source, None);
+
+        let cli = Cli::try_parse_from(["swap-data-fetcher", "migrate"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Migrate)));
+
+        let sources =
+            SourcesConfig::parse(include_str!("../../sources.toml"), "sources.toml").unwrap();
+        assert_eq!(
+            select_sources(&sources, None).unwrap().len(),
+            sources.sources.len()
+        );
+        assert_eq!(
+            select_sources(&sources, Some("btc-native")).unwrap()[0].name,
+            "btc-native"
+        );
+        let unknown = select_sources(&sources, Some("eth-native")).unwrap_err();
+        assert_eq!(unknown.exit_code(), 2);
+        assert_eq!(
+            CliError::Failed("reconcile failed".to_string()).exit_code(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_migrations() {
+        let migrator = sqlx::migrate!("./migrations");
+        let versions: Vec<i64> = migrator.iter().map(|migration| migration.version).collect();
+        assert_eq!(versions, (1..=versions.len() as i64).collect::<Vec<_>>());
+        // Tables the code reads and writes must be created by a migration
+        let sql: String = migrator
+            .iter()
+            .map(|migration| migration.sql.to_string())
+            .collect();
+        for table in [
+            "native_swaps_thorchain",
+            "swap_history_test",
+            "btc_user_data",
+            "btc_closing_prices",
+            "chainflip_swaps_detailed",
+            "pending_swaps",
+            "dead_letter_swaps",
+            "daily_volume_rollups",
+            "closing_prices",
+            "coingecko_coin_ids",
+            "price_candles",
+            "sync_cursors",
+        ] {
+            assert!(
+                sql.contains(&format!("CREATE TABLE IF NOT EXISTS {} (", table)),
+                "no migration creates {}",
+                table
+            );
+        }
+        assert!(sql.contains("CREATE OR REPLACE VIEW unified_swaps AS"));
+    }
+
+    #[test]
+    fn test_fetch_prices_range() {
+        let backfill_start = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let last_closed = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let cli = Cli::try_parse_from([
+            "swap-data-fetcher",
+            "fetch-prices",
+            "--from",
+            "2025-01-01",
+            "--candles",
+        ])
+        .unwrap();
+        let Some(Command::FetchPrices(args)) = cli.command else {
+            panic!("expected fetch-prices subcommand");
+        };
+        assert!(args.candles);
+        assert_eq!(
+            args.range(backfill_start, last_closed).unwrap(),
+            (NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), last_closed)
+        );
+
+        let cli = Cli::try_parse_from(["swap-data-fetcher", "fetch-prices", "--to", "2025-03-02"])
+            .unwrap();
+        let Some(Command::FetchPrices(args)) = cli.command else {
+            panic!("expected fetch-prices subcommand");
+        };
+        assert_eq!(
+            args.range(backfill_start, last_closed)
+                .unwrap_err()
+                .exit_code(),
+            2
+        );
+
+        let cli = Cli::try_parse_from([
+            "swap-data-fetcher",
+            "fetch-prices",
+            "--from",
+            "2025-02-01",
+            "--to",
+            "2025-01-01",
+        ])
+        .unwrap();
+        let Some(Command::FetchPrices(args)) = cli.command else {
+            panic!("expected fetch-prices subcommand");
+        };
+        assert!(args.range(backfill_start, last_closed).is_err());
+
+        let cli = Cli::try_parse_from(["swap-data-fetcher", "fetch-prices"]).unwrap();
+        let Some(Command::FetchPrices(args)) = cli.command else {
+            panic!("expected fetch-prices subcommand");
+        };
+        assert_eq!(
+            args.range(backfill_start, last_closed).unwrap(),
+            (backfill_start, last_closed)
+        );
+    }
+
+    #[test]
+    fn test_sync_direction() {
+        assert_eq!(SyncDirection::Backward.as_str(), "backward");
+        assert_eq!(SyncDirection::Forward.as_str(), "forward");
+    }
+
+    fn pending_swap(attempt_count: i32, age: Duration) -> PendingSwap {
+        PendingSwap {
+            tx_id: "TXID".to_string(),
+            source: "btc-native".to_string(),
+            swap_type: "NATIVE".to_string(),
+            first_seen_at: Utc::now() - age,
+            last_attempt_at: None,
+            attempt_count,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_keeps_fresh_swaps() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            max_age: Duration::hours(24),
+        };
+        let pending = pending_swap(3, Duration::hours(1));
+        assert!(policy.expiry_reason(&pending, Utc::now()).is_none());
+    }
+
+    #[test]
+    fn test_retry_policy_expires_by_attempts_and_age() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            max_age: Duration::hours(24),
+        };
+        let too_many_attempts = pending_swap(10, Duration::hours(1));
+        assert!(policy
+            .expiry_reason(&too_many_attempts, Utc::now())
+            .is_some());
+
+        let too_old = pending_swap(0, Duration::hours(25));
+        assert!(policy.expiry_reason(&too_old, Utc::now()).is_some());
+    }
+
+    // Keeps pending swaps in memory so the tracker can be exercised without a database
+    #[derive(Default)]
+    struct MemoryPendingStore {
+        pending: Mutex<HashMap<(String, String), PendingSwap>>,
+        dead_letter: Mutex<Vec<DeadLetterSwap>>,
+    }
+
+    #[async_trait]
+    impl PendingStore for MemoryPendingStore {
+        async fn insert(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError> {
+            let key = (tx_id.to_string(), source.name.clone());
+            self.pending
+                .lock()
+                .await
+                .entry(key)
+                .or_insert_with(|| PendingSwap {
+                    tx_id: tx_id.to_string(),
+                    source: source.name.clone(),
+                    swap_type: source.swap_type.as_str().to_string(),
+                    first_seen_at: Utc::now(),
+                    last_attempt_at: None,
+                    attempt_count: 0,
+                    last_error: None,
+                });
+            Ok(())
+        }
+
+        async fn fetch(&self, source: &SourceConfig) -> Result<Vec<PendingSwap>, SqlxError> {
+            let mut pending: Vec<PendingSwap> = self
+                .pending
+                .lock()
+                .await
+                .values()
+                .filter(|pending| pending.source == source.name)
+                .cloned()
+                .collect();
+            pending.sort_by_key(|pending| pending.first_seen_at);
+            Ok(pending)
+        }
+
+        async fn record_attempt(
+            &self,
+            tx_id: &str,
+            source: &SourceConfig,
+            error: Option<&str>,
+        ) -> Result<(), SqlxError> {
+            let key = (tx_id.to_string(), source.name.clone());
+            if let Some(pending) = self.pending.lock().await.get_mut(&key) {
+                pending.attempt_count += 1;
+                pending.last_attempt_at = Some(Utc::now());
+                pending.last_error = error.map(str::to_string);
+            }
+            Ok(())
+        }
+
+        async fn delete(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError> {
+            let key = (tx_id.to_string(), source.name.clone());
+            self.pending.lock().await.remove(&key);
+            Ok(())
+        }
+
+        async fn move_to_dead_letter(
+            &self,
+            tx_id: &str,
+            source: &SourceConfig,
+            reason: &str,
+        ) -> Result<(), SqlxError> {
+            let key = (tx_id.to_string(), source.name.clone());
+            if let Some(pending) = self.pending.lock().await.remove(&key) {
+                self.dead_letter.lock().await.push(DeadLetterSwap {
+                    tx_id: pending.tx_id,
+                    source: pending.source,
+                    swap_type: pending.swap_type,
+                    first_seen_at: pending.first_seen_at,
+                    last_attempt_at: pending.last_attempt_at,
+                    attempt_count: pending.attempt_count,
+                    last_error: pending.last_error,
+                    reason: reason.to_string(),
+                    dead_lettered_at: Utc::now(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn memory_tracker() -> PendingTracker {
+        PendingTracker::new(Arc::new(MemoryPendingStore::default()))
+    }
+
+    fn source(name: &str) -> SourceConfig {
+        SourcesConfig::parse(include_str!("../../sources.toml"), "sources.toml")
+            .unwrap()
+            .sources
+            .into_iter()
+            .find(|source| source.name == name)
+            .unwrap()
+    }
 
     #[test]
-    fn test_read_next_page_token_from_file() {
-        let token = read_next_page_token_from_file(TOKEN_FILE_PATH).unwrap_or("default".to_string());
-        assert!(token.is_empty() || token == "170981189000000012" || token == "default");
+    fn test_sources_config() {
+        let native = source("btc-native");
+        assert_eq!(native.swap_type, SwapType::NATIVE);
+        assert_eq!(native.table, "native_swaps_thorchain");
+        assert_eq!(
+            native.base_url(),
+            "https://vanaheimex.com/actions?asset=notrade,BTC.BTC&type=swap"
+        );
+        assert_eq!(source("btc-trade").retry_interval_secs, 300);
+
+        let invalid_table = r#"
+            [[sources]]
+            name = "eth-native"
+            endpoint = "https://vanaheimex.com/actions"
+            asset = "notrade,ETH.ETH"
+            swap_type = "NATIVE"
+            table = "eth; DROP TABLE users"
+            poll_interval_secs = 300
+        "#;
+        assert!(SourcesConfig::parse(invalid_table, "test.toml").is_err());
     }
 
     #[test]
-    fn test_write_next_page_token_to_file() {
-        let test_token = "test_token";
-        write_next_page_token_to_file(test_token,TOKEN_FILE_PATH).unwrap();
+    fn test_config() {
+        let config = Config::parse(include_str!("../../config.toml"), "config.toml").unwrap();
+        assert_eq!(
+            config,
+            Config {
+                database_url: config.database_url.clone(),
+                ..Config::default()
+            }
+        );
+        assert_eq!(config.bind_address, DEFAULT_BIND_ADDRESS);
+        assert_eq!(config.midgard.rate_limit_delay_ms, 5000);
+        assert_eq!(config.chainflip.page_size, 30);
+
+        let partial = r#"
+            database_url = "postgres://localhost/swaps"
+
+            [chainflip]
+            page_size = 50
+        "#;
+        let config = Config::parse(partial, "test.toml").unwrap();
+        assert_eq!(config.chainflip.page_size, 50);
+        assert_eq!(config.chainflip.max_attempts, 10);
+        assert_eq!(config.jobs.valuation_interval_secs, 3600);
+        assert_eq!(config.pending.max_attempts, 500);
+        assert_eq!(
+            config.prices.providers,
+            ["coingecko", "midgard", "chainflip"]
+        );
+        assert_eq!(config.admin.token(), None);
+        assert!(config.validate().is_ok());
+
+        let prices = r#"
+            [prices]
+            providers = ["midgard"]
+            backfill_start = "2024-06-01"
+            candles_enabled = true
+
+            [admin]
+            token = ""
+        "#;
+        let config = Config::parse(prices, "test.toml").unwrap();
+        assert_eq!(config.prices.providers, ["midgard"]);
+        assert_eq!(
+            config.prices.backfill_start(),
+            NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+        );
+        assert!(config.prices.candles_enabled);
+        assert_eq!(config.admin.token(), None);
+        assert!(Config::parse("[prices]\nbackfill_start = \"June\"", "test.toml").is_err());
+
+        let mut config = Config::parse(partial, "test.toml").unwrap();
+        let mut unknown_provider = config.clone();
+        unknown_provider.prices.providers = vec!["binance".to_string()];
+        assert!(unknown_provider.validate().is_err());
+
+        let mut no_providers = config.clone();
+        no_providers.prices.providers.clear();
+        assert!(no_providers.validate().is_err());
+
+        let mut zero_attempts = config.clone();
+        zero_attempts.pending.max_attempts = 0;
+        assert!(zero_attempts.validate().is_err());
+
+        config.admin.token = Some("secret".to_string());
+        assert_eq!(config.admin.token(), Some("secret"));
+
+        let mut missing_url = config.clone();
+        missing_url.database_url = String::new();
+        assert!(missing_url.validate().is_err());
+
+        let mut bad_bind = config.clone();
+        bad_bind.bind_address = "localhost".to_string();
+        assert!(bad_bind.validate().is_err());
+
+        let mut bad_page_size = config.clone();
+        bad_page_size.chainflip.page_size = 0;
+        assert!(bad_page_size.validate().is_err());
+
+        let mut zero_interval = config.clone();
+        zero_interval.jobs.volume_rollup_interval_secs = 0;
+        assert!(zero_interval.validate().is_err());
+
+        let mut short_window = config.clone();
+        short_window.jobs.volume_rollup_window_days = 1;
+        assert!(short_window.validate().is_err());
+
+        assert!(Config::parse("[midgard]\nmax_attempts = \"ten\"", "test.toml").is_err());
+    }
+
+    fn swap_action(tx_id: &str, status: &str) -> SwapTransaction {
+        serde_json::from_value(serde_json::json!({
+            "date": "1734331990000000000",
+            "in": [{
+                "address": "bc1qsender",
+                "coins": [{ "amount": "100000000", "asset": "BTC.BTC" }],
+                "txID": tx_id
+            }],
+            "out": [{
+                "address": "0xreceiver",
+                "coins": [{ "amount": "3000000000", "asset": "ETH.ETH" }],
+                "txID": ""
+            }],
+            "metadata": { "swap": { "inPriceUSD": "100000", "outPriceUSD": "3300" } },
+            "pools": ["BTC.BTC", "ETH.ETH"],
+            "status": status
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_insert_swaps_query() {
+        let handler = TransactionHandler::new(memory_tracker());
+        let mut records = Vec::new();
+        for tx_id in ["FIRSTTX", "SECONDTX"] {
+            records.push(
+                handler
+                    .parse_transaction(&swap_action(tx_id, "success"))
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let query = PostgreSQL::insert_swaps_query("native_swaps_thorchain", records);
+        let sql = query.sql();
+        assert!(sql.starts_with("INSERT INTO native_swaps_thorchain ("));
+        assert!(sql.ends_with(" ON CONFLICT (tx_id) DO NOTHING"));
+        assert_eq!(sql.matches("CAST($").count(), 2);
+        assert!(sql.contains("CAST($14 AS JSONB)"));
+        assert!(sql.contains("CAST($38 AS JSONB)"));
+        assert!(sql.contains("$48)"));
+        assert!(!sql.contains("$49"));
+
+        let mut summary = InsertSummary::new(2, 1);
+        assert_eq!(
+            summary,
+            InsertSummary {
+                inserted: 1,
+                conflicts: 1
+            }
+        );
+        summary.add(InsertSummary::new(3, 3));
+        assert_eq!(summary.inserted, 4);
+        assert_eq!(summary.conflicts, 1);
+
+        let chunks = PostgreSQL::insert_chunks((0..2 * INSERT_CHUNK_SIZE + 500).collect());
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            [INSERT_CHUNK_SIZE, INSERT_CHUNK_SIZE, 500]
+        );
+        assert_eq!(chunks[1][0], INSERT_CHUNK_SIZE);
+        assert_eq!(PostgreSQL::insert_chunks(vec![1]), [[1]]);
+        assert!(PostgreSQL::insert_chunks(Vec::<usize>::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_parse_transaction_swap_metadata() {
+        let mut action = swap_action("STREAMINGTX", "success");
+        action.metadata.swap = serde_json::from_value(serde_json::json!({
+            "inPriceUSD": "100000",
+            "outPriceUSD": "3300",
+            "networkFees": [{ "amount": "120000", "asset": "ETH.ETH" }],
+            "liquidityFee": "2500000",
+            "swapSlip": "12",
+            "affiliateFee": "15",
+            "affiliateAddress": "thor1affiliate",
+            "memo": "=:ETH.ETH:0xreceiver",
+            "isStreamingSwap": true,
+            "streamingSwapMeta": { "count": "4", "quantity": "4", "interval": "1" }
+        }))
+        .unwrap();
+
+        let handler = TransactionHandler::new(memory_tracker());
+        let parsed = handler.parse_transaction(&action).await.unwrap();
+        assert_eq!(parsed.in_amount_usd, Some(100000.0));
+        assert_eq!(parsed.out_amount_usd, Some(99000.0));
+        assert_eq!(parsed.liquidity_fee, Some(0.025));
+        assert_eq!(parsed.swap_slip_bps, Some(12));
+        assert_eq!(parsed.affiliate_fee_bps, Some(15));
+        assert_eq!(parsed.affiliate_address.as_deref(), Some("thor1affiliate"));
+        assert_eq!(parsed.is_streaming_swap, Some(true));
+        assert_eq!(parsed.streaming_quantity, Some(4));
+        assert_eq!(parsed.streaming_interval, Some(1));
+        assert_eq!(
+            parsed.network_fees.as_deref(),
+            Some(r#"[{"amount":0.0012,"asset":"ETH.ETH"}]"#)
+        );
+
+        let minimal = handler
+            .parse_transaction(&swap_action("PLAINTX", "success"))
+            .await
+            .unwrap();
+        assert!(minimal.network_fees.is_none());
+        assert!(minimal.is_streaming_swap.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pending_transaction_reaches_retry_loop() {
+        let pending_tracker = memory_tracker();
+        let handler = TransactionHandler::new(pending_tracker.clone());
+        let native = source("btc-native");
+        let actions = vec![
+            swap_action("PENDINGTX", "pending"),
+            swap_action("DONETX", "success"),
+        ];
+
+        let processed = handler
+            .process_transactions(&actions, &native)
+            .await
+            .unwrap();
+        assert_eq!(processed.len(), 1);
+        assert_eq!(processed[0].tx_id, "DONETX");
+
+        let due = pending_tracker
+            .due_for_retry(&native, &RetryPolicy::default())
+            .await
+            .unwrap();
+        let due_ids: Vec<&str> = due.iter().map(|pending| pending.tx_id.as_str()).collect();
+        assert_eq!(due_ids, vec!["PENDINGTX"]);
+
+        let other_source = pending_tracker
+            .due_for_retry(&source("btc-trade"), &RetryPolicy::default())
+            .await
+            .unwrap();
+        assert!(other_source.is_empty());
+    }
+
+    // Database tests run against TEST_DATABASE_URL and are skipped when it isn't set
+    async fn test_pg() -> Option<PostgreSQL> {
+        let database_url = std::env::var("TEST_DATABASE_URL").ok()?;
+        let pg = PostgreSQL::init(&database_url).await.unwrap();
+        pg.migrate().await.unwrap();
+        Some(pg)
+    }
+
+    async fn find_pending(
+        pending_tracker: &PendingTracker,
+        tx_id: &str,
+        source: &SourceConfig,
+    ) -> Option<PendingSwap> {
+        pending_tracker
+            .pending(source)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|pending| pending.tx_id == tx_id)
+    }
+
+    #[tokio::test]
+    async fn test_postgres_pending_swap_lifecycle() {
+        let Some(pg) = test_pg().await else {
+            return;
+        };
+        let pending_tracker = PendingTracker::postgres(pg.clone());
+        let native = source("btc-native");
+        let tx_id = "PGLIFECYCLETX";
+        pg.delete_pending_swap(tx_id, &native.name).await.unwrap();
+
+        pending_tracker.track(tx_id, &native).await.unwrap();
+        pending_tracker
+            .record_attempt(tx_id, &native, Some("Transaction not found"))
+            .await
+            .unwrap();
+        // Tracking a swap that is already pending keeps its attempts
+        pending_tracker.track(tx_id, &native).await.unwrap();
+
+        let due = pending_tracker
+            .due_for_retry(&native, &RetryPolicy::default())
+            .await
+            .unwrap();
+        let pending = due.iter().find(|pending| pending.tx_id == tx_id).unwrap();
+        assert_eq!(pending.swap_type, "NATIVE");
+        assert_eq!(pending.attempt_count, 1);
+        assert_eq!(pending.last_error.as_deref(), Some("Transaction not found"));
+        assert!(pending.last_attempt_at.is_some());
+
+        // A successful attempt clears the previous error
+        pending_tracker
+            .record_attempt(tx_id, &native, None)
+            .await
+            .unwrap();
+        let pending = find_pending(&pending_tracker, tx_id, &native)
+            .await
+            .unwrap();
+        assert_eq!(pending.attempt_count, 2);
+        assert_eq!(pending.last_error, None);
+
+        pending_tracker.settle(tx_id, &native).await.unwrap();
+        assert!(find_pending(&pending_tracker, tx_id, &native)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_postgres_pending_swap_dead_letter() {
+        let Some(pg) = test_pg().await else {
+            return;
+        };
+        let pending_tracker = PendingTracker::postgres(pg.clone());
+        let trade = source("btc-trade");
+        let tx_id = "PGDEADLETTERTX";
+        pg.delete_pending_swap(tx_id, &trade.name).await.unwrap();
+
+        pending_tracker.track(tx_id, &trade).await.unwrap();
+        pending_tracker
+            .record_attempt(tx_id, &trade, Some("Midgard timeout"))
+            .await
+            .unwrap();
+        let policy = RetryPolicy {
+            max_attempts: 1,
+            max_age: Duration::hours(24),
+        };
+        let due = pending_tracker
+            .due_for_retry(&trade, &policy)
+            .await
+            .unwrap();
+        assert!(due.iter().all(|pending| pending.tx_id != tx_id));
+        assert!(find_pending(&pending_tracker, tx_id, &trade)
+            .await
+            .is_none());
+
+        let dead_letter = pg
+            .fetch_dead_letter_swaps(Some(trade.name.clone()))
+            .await
+            .unwrap();
+        let dead = dead_letter.iter().find(|dead| dead.tx_id == tx_id).unwrap();
+        assert_eq!(dead.attempt_count, 1);
+        assert_eq!(dead.last_error.as_deref(), Some("Midgard timeout"));
+        assert_eq!(dead.reason, "Exceeded max attempts (1/1)");
+    }
+
+    #[tokio::test]
+    async fn test_postgres_insert_bulk_conflicts() {
+        let Some(pg) = test_pg().await else {
+            return;
+        };
+        let table = "native_swaps_thorchain";
+        let prefix = format!("INSERTTEST{}", Utc::now().timestamp_nanos_opt().unwrap());
+        let handler = TransactionHandler::new(memory_tracker());
+        let mut template = handler
+            .parse_transaction(&swap_action(&prefix, "success"))
+            .await
+            .unwrap();
+        template.memo = Some("=:ETH.ETH:0xabc:0/1/0:référence".to_string());
+        template.affiliate_address = Some("thor1affiliäte".to_string());
+        let record = |n: usize| {
+            let mut record = template.clone();
+            record.tx_id = format!("{}-{}", prefix, n);
+            record
+        };
+
+        // One more row than a chunk, so the batch takes two INSERTs
+        let records: Vec<_> = (0..=INSERT_CHUNK_SIZE).map(record).collect();
+        let summary = pg.insert_bulk(table, records).await.unwrap();
+        assert_eq!(
+            summary,
+            InsertSummary {
+                inserted: INSERT_CHUNK_SIZE as u64 + 1,
+                conflicts: 0
+            }
+        );
+
+        let records = vec![record(0), record(1), record(INSERT_CHUNK_SIZE + 1)];
+        let summary = pg.insert_bulk(table, records).await.unwrap();
+        assert_eq!(
+            summary,
+            InsertSummary {
+                inserted: 1,
+                conflicts: 2
+            }
+        );
+
+        let stored = pg
+            .fetch_swap_by_tx_id(table, "btc-native", &record(0).tx_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.memo, template.memo);
+        assert_eq!(stored.affiliate_address, template.affiliate_address);
+
+        sqlx::query("DELETE FROM native_swaps_thorchain WHERE tx_id LIKE $1")
+            .bind(format!("{}-%", prefix))
+            .execute(&pg.pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_postgres_swap_table_columns() {
+        let Some(pg) = test_pg().await else {
+            return;
+        };
+
+        for table in ["native_swaps_thorchain", "swap_history_test"] {
+            assert_eq!(
+                pg.missing_swap_columns(table).await.unwrap(),
+                Vec::<String>::new(),
+                "{} is missing swap columns",
+                table
+            );
+        }
+        let missing = pg.missing_swap_columns("no_such_table").await.unwrap();
+        assert_eq!(missing.len(), SWAP_TABLE_COLUMNS.len());
+        assert_eq!(missing[0], "timestamp");
+
+        // btc_user_data isn't a sync source, but /swaps reads the valuation columns from it
+        let query = RequestBody::default().validate().unwrap();
+        pg.fetch_all(SWAP_HISTORY_TABLE, &query).await.unwrap();
+        pg.count_all(SWAP_HISTORY_TABLE, &query).await.unwrap();
+        let tx_id = format!("HISTORYTEST{}", Utc::now().timestamp_nanos_opt().unwrap());
+        assert!(pg
+            .fetch_swap_history_by_tx_id(&tx_id)
+            .await
+            .unwrap()
+            .is_none());
+        sqlx::query(
+            "INSERT INTO btc_user_data (timestamp, date, time, tx_id, in_asset, in_amount, \
+             in_address, out_asset_1, out_amount_1, out_address_1) \
+             VALUES (1735689600, '2025-01-01', '00:00:00', $1, 'BTC.BTC', 1, 'bc1q', 'ETH.ETH', 30, '0x')",
+        )
+        .bind(&tx_id)
+        .execute(&pg.pool)
+        .await
+        .unwrap();
+        let stored = pg
+            .fetch_swap_history_by_tx_id(&tx_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, "success");
+        sqlx::query("DELETE FROM btc_user_data WHERE tx_id = $1")
+            .bind(&tx_id)
+            .execute(&pg.pool)
+            .await
+            .unwrap();
+    }
+
+    async fn btc_valuation(pg: &PostgreSQL, tx_id: &str) -> (f64, f64, String) {
+        sqlx::query_as(
+            "SELECT btc_price_usd, btc_value_usd, btc_price_source FROM btc_user_data \
+             WHERE tx_id = $1",
+        )
+        .bind(tx_id)
+        .fetch_one(&pg.pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_postgres_btc_valuation_sources() {
+        let Some(pg) = test_pg().await else {
+            return;
+        };
+        let cleanup = |pg: PostgreSQL| async move {
+            for sql in [
+                "DELETE FROM btc_user_data WHERE tx_id LIKE 'VALUATIONTEST%'",
+                "DELETE FROM btc_closing_prices WHERE date IN ('2001-01-01', '2001-01-02')",
+                "DELETE FROM price_candles WHERE asset = 'BTC' AND open_time < '2001-01-03'",
+            ] {
+                sqlx::query(sql).execute(&pg.pool).await.unwrap();
+            }
+        };
+        cleanup(pg.clone()).await;
+
+        // 2001-01-01 01:00 UTC has a close only, 2001-01-02 01:30 UTC a candle only
+        sqlx::query(
+            "INSERT INTO btc_user_data (timestamp, date, time, tx_id, in_asset, in_amount, \
+             in_address, out_asset_1, out_amount_1, out_address_1) VALUES \
+             (978310800, '2001-01-01', '01:00:00', 'VALUATIONTESTCLOSE', 'BTC.BTC', 2, 'bc1q', 'ETH.ETH', 30, '0x'), \
+             (978399000, '2001-01-02', '01:30:00', 'VALUATIONTESTCANDLE', 'BTC.BTC', 1, 'bc1q', 'ETH.ETH', 15, '0x')",
+        )
+        .execute(&pg.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO btc_closing_prices (date, closing_price_usd) VALUES ('2001-01-01', 1000)",
+        )
+        .execute(&pg.pool)
+        .await
+        .unwrap();
+        let insert_candle = "INSERT INTO price_candles (asset, open_time, open, high, low, close) \
+                             VALUES ('BTC', TO_TIMESTAMP($1), $2, $2, $2, $2)";
+        sqlx::query(insert_candle)
+            .bind(978397200.0)
+            .bind(2000.0)
+            .execute(&pg.pool)
+            .await
+            .unwrap();
+
+        let summary = pg.apply_btc_valuation("btc_user_data").await.unwrap();
+        assert!(summary.valued >= 2);
+        assert_eq!(summary.earliest_date, NaiveDate::from_ymd_opt(2001, 1, 1));
+        assert_eq!(
+            btc_valuation(&pg, "VALUATIONTESTCLOSE").await,
+            (1000.0, 2000.0, "close".to_string())
+        );
+        assert_eq!(
+            btc_valuation(&pg, "VALUATIONTESTCANDLE").await,
+            (2000.0, 2000.0, "candle".to_string())
+        );
+
+        // Once the candle for its hour arrives the close-priced swap is revalued
+        sqlx::query(insert_candle)
+            .bind(978310800.0)
+            .bind(1100.0)
+            .execute(&pg.pool)
+            .await
+            .unwrap();
+        let summary = pg.apply_btc_valuation("btc_user_data").await.unwrap();
+        assert_eq!(summary.earliest_date, NaiveDate::from_ymd_opt(2001, 1, 1));
+        assert_eq!(
+            btc_valuation(&pg, "VALUATIONTESTCLOSE").await,
+            (1100.0, 2200.0, "candle".to_string())
+        );
+
+        cleanup(pg).await;
+    }
+
+    #[tokio::test]
+    async fn test_pending_tracker_settles_and_dead_letters() {
+        let store = Arc::new(MemoryPendingStore::default());
+        let pending_tracker = PendingTracker::new(store.clone());
+        let trade = source("btc-trade");
+        pending_tracker.track("SETTLED", &trade).await.unwrap();
+        pending_tracker.track("STUCK", &trade).await.unwrap();
+
+        pending_tracker.settle("SETTLED", &trade).await.unwrap();
+        pending_tracker
+            .record_attempt("STUCK", &trade, Some("Transaction not found"))
+            .await
+            .unwrap();
+
+        let policy = RetryPolicy {
+            max_attempts: 1,
+            max_age: Duration::hours(24),
+        };
+        let due = pending_tracker
+            .due_for_retry(&trade, &policy)
+            .await
+            .unwrap();
+        assert!(due.is_empty());
 
-        let read_token = read_next_page_token_from_file(TOKEN_FILE_PATH).unwrap();
-        assert_eq!(read_token, test_token);
-        fs::remove_file(TOKEN_FILE_PATH).unwrap();
+        let dead_letter = store.dead_letter.lock().await;
+        assert_eq!(dead_letter.len(), 1);
+        assert_eq!(dead_letter[0].tx_id, "STUCK");
+        assert_eq!(dead_letter[0].attempt_count, 1);
+        assert_eq!(
+            dead_letter[0].last_error.as_deref(),
+            Some("Transaction not found")
+        );
     }
 }
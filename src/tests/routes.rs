@@ -0,0 +1,404 @@
+use actix_web::{
+    http::StatusCode,
+    test::{call_service, init_service, read_body_json, TestRequest},
+    web::Data,
+    App,
+};
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use std::time::Duration;
+
+use crate::{
+    config::{AdminConfig, ChainflipConfig, MidgardConfig, PricesConfig, SourcesConfig},
+    db::PostgreSQL,
+    models::pagination::Paginated,
+    routes::{
+        self,
+        admin::BackfillRequest,
+        chainflip_swaps::{ChainflipSortField, ChainflipSwapsRequest},
+        pagination::DEFAULT_LIMIT,
+        stats::{StatsInterval, VolumeStatsRequest},
+        swap_history::{OrderType, RequestBody, SortField, SwapCursor},
+    },
+    utils::{chainflip::ChainFlip, midgard::MidGard, price_provider::PriceProviderChain},
+};
+
+// Requests in these tests are rejected before any query runs, so the pool never connects
+fn lazy_pg() -> PostgreSQL {
+    PostgreSQL {
+        pool: PgPool::connect_lazy("postgres://localhost/unused").unwrap(),
+    }
+}
+
+// Nothing listens on this port, so every query fails after a short acquire timeout
+fn unreachable_pg() -> PostgreSQL {
+    PostgreSQL {
+        pool: PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://localhost:1/unused")
+            .unwrap(),
+    }
+}
+
+#[test]
+fn test_sort_field_whitelist() {
+    assert_eq!(SortField::parse("timestamp"), Some(SortField::Timestamp));
+    assert_eq!(
+        SortField::parse("out_amount_1"),
+        Some(SortField::OutAmount1)
+    );
+    assert_eq!(
+        SortField::parse("timestamp; DROP TABLE btc_user_data"),
+        None
+    );
+    assert_eq!(SortField::parse("TIMESTAMP"), None);
+}
+
+#[actix_web::test]
+async fn test_swaps_rejects_unknown_sort_by() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(lazy_pg()))
+            .configure(routes::swap_history::init),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/swaps")
+        .set_json(json!({
+            "sort_by": "timestamp; DROP TABLE btc_user_data; --",
+            "page": "1",
+            "limit": "10",
+            "order": "DESC"
+        }))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["error"], "invalid_sort_by");
+    assert!(body["message"].as_str().unwrap().contains("out_amount_1"));
+}
+
+#[actix_web::test]
+async fn test_swaps_rejects_invalid_params_per_field() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(lazy_pg()))
+            .configure(routes::swap_history::init),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/swaps")
+        .set_json(json!({
+            "sort_by": "timestamp",
+            "page": "0",
+            "limit": "abc",
+            "order": "sideways",
+            "date": "14-08-2023"
+        }))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["error"], "validation_failed");
+    let fields: Vec<&str> = body["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["page", "limit", "order", "date"]);
+
+    let req = TestRequest::get()
+        .uri("/swaps?page=2&limit=100000")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["fields"][0]["field"], "limit");
+}
+
+#[test]
+fn test_swaps_request_validation() {
+    let body: RequestBody = serde_json::from_value(json!({
+        "sort_by": "in_amount",
+        "page": 3,
+        "limit": "20",
+        "order": "asc",
+        "date": "2025-01-01"
+    }))
+    .unwrap();
+    let query = body.validate().unwrap();
+    assert_eq!(query.sort_by, SortField::InAmount);
+    assert_eq!(query.order, OrderType::ASC);
+    assert_eq!(query.offset(), 40);
+    assert_eq!(query.date, NaiveDate::from_ymd_opt(2025, 1, 1));
+
+    let defaults = RequestBody::default().validate().unwrap();
+    assert_eq!(defaults.page, 1);
+    assert_eq!(defaults.limit, DEFAULT_LIMIT);
+    assert_eq!(defaults.order, OrderType::DESC);
+}
+
+#[test]
+fn test_swap_cursor_and_pagination() {
+    let cursor = SwapCursor::parse("1734331990:ABCDEF").unwrap();
+    assert_eq!(cursor.timestamp, 1734331990);
+    assert_eq!(cursor.tx_id, "ABCDEF");
+    assert_eq!(cursor.encode(), "1734331990:ABCDEF");
+    assert!(SwapCursor::parse("not-a-cursor").is_none());
+    assert!(SwapCursor::parse("1734331990:").is_none());
+
+    let page = Paginated::from_rows(vec![1, 2, 3], 1, 2, 3);
+    assert_eq!(page.data, vec![1, 2]);
+    assert!(page.has_next);
+    let last_page = Paginated::from_rows(vec![3], 2, 2, 3);
+    assert!(!last_page.has_next);
+
+    let body: RequestBody = serde_json::from_value(json!({
+        "sort_by": "in_amount",
+        "cursor": "1734331990:ABCDEF"
+    }))
+    .unwrap();
+    assert!(body.validate().is_err());
+}
+
+#[actix_web::test]
+async fn test_chainflip_swaps_validation() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(lazy_pg()))
+            .configure(routes::chainflip_swaps::init),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri("/chainflip/swaps?sort_by=broker")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = TestRequest::get()
+        .uri("/chainflip/swaps?page=0&from=2025-02-01&to=2025-01-01")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = read_body_json(resp).await;
+    let fields: Vec<&str> = body["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["page", "to"]);
+
+    let request: ChainflipSwapsRequest = serde_json::from_value(json!({
+        "sort_by": "output_value_usd",
+        "source_asset": " btc ",
+        "status": "completed",
+        "address": ""
+    }))
+    .unwrap();
+    let query = request.validate().unwrap();
+    assert_eq!(query.sort_by, ChainflipSortField::OutputValueUsd);
+    assert_eq!(query.source_asset.as_deref(), Some("BTC"));
+    assert_eq!(query.status.as_deref(), Some("COMPLETED"));
+    assert_eq!(query.address, None);
+}
+
+#[actix_web::test]
+async fn test_swap_lookup_routes() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(lazy_pg()))
+            .app_data(Data::new(MidGard::new(&MidgardConfig::default()).unwrap()))
+            .app_data(Data::new(
+                ChainFlip::new(&ChainflipConfig::default()).unwrap(),
+            ))
+            .app_data(Data::new(SourcesConfig { sources: vec![] }))
+            .configure(routes::unified_swaps::init)
+            .configure(routes::chainflip_swaps::init)
+            .configure(routes::swap_lookup::init),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri("/chainflip/swaps/not-a-number")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["error"], "invalid_swap_id");
+
+    for tx_id in ["not-a-tx-id", "ABCD", &"G".repeat(64), &"A".repeat(65)] {
+        let req = TestRequest::get()
+            .uri(&format!("/swaps/{}", tx_id))
+            .to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = read_body_json(resp).await;
+        assert_eq!(body["error"], "invalid_tx_id");
+    }
+
+    // /swaps/unified must still reach its own handler rather than the tx_id lookup
+    let req = TestRequest::get()
+        .uri("/swaps/unified?page=0&limit=1000")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["fields"][0]["field"], "page");
+    assert_eq!(body["fields"][1]["field"], "limit");
+
+    // A page this large would overflow the OFFSET bound to Postgres
+    let req = TestRequest::get()
+        .uri(&format!("/swaps/unified?page={}", u64::MAX))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["fields"][0]["field"], "page");
+}
+
+#[actix_web::test]
+async fn test_database_errors_return_internal_error() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(unreachable_pg()))
+            .app_data(Data::new(MidGard::new(&MidgardConfig::default()).unwrap()))
+            .app_data(Data::new(SourcesConfig { sources: vec![] }))
+            .configure(routes::unified_swaps::init)
+            .configure(routes::swap_lookup::init),
+    )
+    .await;
+
+    for uri in [
+        "/swaps/unified".to_string(),
+        format!("/swaps/{}", "A".repeat(64)),
+    ] {
+        let req = TestRequest::get().uri(&uri).to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR, "{}", uri);
+        let body: Value = read_body_json(resp).await;
+        assert_eq!(body["error"], "internal_error");
+    }
+}
+
+#[actix_web::test]
+async fn test_address_swaps_validation() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(lazy_pg()))
+            .configure(routes::addresses::init),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri("/addresses/bc1qexample/swaps?page=0&limit=0")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = read_body_json(resp).await;
+    assert_eq!(body["fields"][0]["field"], "page");
+    assert_eq!(body["fields"][1]["field"], "limit");
+
+    let req = TestRequest::get()
+        .uri("/addresses/bc1qexample/summary?protocol=maya")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_volume_stats_validation() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(lazy_pg()))
+            .configure(routes::stats::init),
+    )
+    .await;
+
+    let req = TestRequest::get()
+        .uri("/stats/volume?interval=year&from=2025-03-01&to=2025-01-01")
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: Value = read_body_json(resp).await;
+    let fields: Vec<&str> = body["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field["field"].as_str().unwrap())
+        .collect();
+    assert_eq!(fields, vec!["interval", "to"]);
+
+    let request: VolumeStatsRequest = serde_json::from_value(json!({
+        "interval": "Week",
+        "to": "2025-04-10"
+    }))
+    .unwrap();
+    let query = request.validate().unwrap();
+    assert_eq!(query.interval, StatsInterval::Week);
+    assert_eq!(query.to, NaiveDate::from_ymd_opt(2025, 4, 10).unwrap());
+    assert_eq!(query.from, NaiveDate::from_ymd_opt(2025, 1, 10).unwrap());
+    assert_eq!(query.protocol, None);
+}
+
+#[actix_web::test]
+async fn test_closing_price_backfill_request() {
+    let app = init_service(
+        App::new()
+            .app_data(Data::new(lazy_pg()))
+            .app_data(Data::new(PriceProviderChain::new(Vec::new())))
+            .app_data(Data::new(AdminConfig {
+                token: Some("secret".to_string()),
+            }))
+            .app_data(Data::new(PricesConfig::default()))
+            .configure(routes::admin::init),
+    )
+    .await;
+
+    let req = TestRequest::post()
+        .uri("/admin/closing-prices/backfill")
+        .set_json(json!({ "from": "2025-01-01" }))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // With the configured token the request reaches validation
+    let req = TestRequest::post()
+        .uri("/admin/closing-prices/backfill")
+        .insert_header(("x-admin-token", "secret"))
+        .set_json(json!({ "from": "not-a-date" }))
+        .to_request();
+    let resp = call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let backfill_start = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+    let today = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+    let request: BackfillRequest = serde_json::from_value(json!({
+        "from": "2025-01-01",
+        "to": "2025-02-01"
+    }))
+    .unwrap();
+    let range = request.validate(backfill_start, today).unwrap();
+    assert_eq!(range.from, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+    assert_eq!(range.to, NaiveDate::from_ymd_opt(2025, 2, 1).unwrap());
+
+    let request: BackfillRequest = serde_json::from_value(json!({
+        "from": "2025-02-01",
+        "to": "2025-03-02"
+    }))
+    .unwrap();
+    assert!(request.validate(backfill_start, today).is_err());
+
+    let range = BackfillRequest::default()
+        .validate(backfill_start, today)
+        .unwrap();
+    assert_eq!(range.from, backfill_start);
+    assert_eq!(range.to, today);
+}
@@ -2,15 +2,15 @@ pub mod coingecko;
 pub mod cron;
 pub mod midgard;
 pub mod chainflip;
+pub mod pending_tracker;
+pub mod price_provider;
 pub mod transaction_handler;
 
+use crate::models::closing_prices::PriceCandle;
 use chrono::{NaiveDate, ParseError, TimeZone, Utc};
 use regex::Regex;
 use std::error::Error;
-use std::fs::{self, OpenOptions};
-use std::io::{self, Write};
 use std::num::{ParseFloatError, ParseIntError};
-use std::path::Path;
 
 pub fn convert_to_standard_unit(amount: f64, decimals: u32) -> f64 {
     let divisor = 10u64.pow(decimals);
@@ -67,27 +67,58 @@ pub fn asset_name_from_trade_pool(pool_name: &str) -> Option<String> {
     }
 }
 
-pub fn format_date_for_sql(date_str: &str) -> Result<String, ParseError> {
-    let date = NaiveDate::parse_from_str(date_str, "%d-%m-%Y")?;
-    Ok(date.format("%Y-%m-%d").to_string())
+// Symbol an asset is priced under, regardless of chain: "ETH.USDC-0XA0B8..." -> "USDC",
+// "BTC~BTC" -> "BTC", and Chainflip's "ARBUSDC" -> "USDC"
+pub fn price_symbol(asset: &str) -> Option<String> {
+    let asset = asset.trim().to_uppercase();
+    let re = Regex::new(r"[./~]").unwrap();
+    let symbol = match re.split(&asset).nth(1) {
+        Some(pool_symbol) => pool_symbol.split('-').next().unwrap_or_default(),
+        None => ["ARB", "SOL", "HUB"]
+            .iter()
+            .find_map(|chain| asset.strip_prefix(chain).filter(|rest| !rest.is_empty()))
+            .unwrap_or(&asset),
+    };
+    if symbol.is_empty() {
+        None
+    } else {
+        Some(symbol.to_string())
+    }
 }
 
-pub fn read_next_page_token_from_file(file_path : &str) -> io::Result<String> {
-    if Path::new(file_path).exists() {
-        fs::read_to_string(file_path).map(|token| token.trim().to_string())
-    } else {
-        Ok(String::from(""))
+// Groups (milliseconds, price) points into hourly OHLC candles, ordered by open time
+pub fn hourly_candles(asset: &str, points: &[(i64, f64)]) -> Vec<PriceCandle> {
+    let mut candles: Vec<PriceCandle> = Vec::new();
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|(timestamp, _)| *timestamp);
+    for (timestamp, price) in sorted {
+        let hour = timestamp.div_euclid(3_600_000) * 3_600;
+        let open_time = match Utc.timestamp_opt(hour, 0).single() {
+            Some(open_time) => open_time,
+            None => continue,
+        };
+        match candles.last_mut() {
+            Some(candle) if candle.open_time == open_time => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+            }
+            _ => candles.push(PriceCandle {
+                asset: asset.to_string(),
+                open_time,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+            }),
+        }
     }
+    candles
 }
 
-pub fn write_next_page_token_to_file(token: &str, file_path : &str) -> io::Result<()> {
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(file_path)?;
-    file.write_all(token.as_bytes())?;
-    Ok(())
+pub fn format_date_for_sql(date_str: &str) -> Result<String, ParseError> {
+    let date = NaiveDate::parse_from_str(date_str, "%d-%m-%Y")?;
+    Ok(date.format("%Y-%m-%d").to_string())
 }
 
 pub fn sanitize_string(input: &str) -> String {
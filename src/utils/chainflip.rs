@@ -1,21 +1,97 @@
-use crate::models::chainflip_swaps::SwapResponse;
+use crate::{
+    config::ChainflipConfig,
+    models::chainflip_swaps::{SwapNode, SwapResponse},
+};
 use reqwest::Client;
 use serde_json::json;
 use std::time::Duration;
 
-pub struct ChainFlip;
+// Fields read into SwapNode, shared by every swap request query
+const SWAP_NODE_FIELDS: &str = r#"
+    node {
+        id
+        swapRequestNativeId
+        sourceAsset
+        destAsset
+        baseAssetLeg1
+        baseAssetLeg2
+        ingressAmount
+        ingressValueUsd
+        egressAmount
+        egressValueUsd
+        inputAmount
+        inputValueUsd
+        intermediateAmount
+        intermediateValueUsd
+        outputAmount
+        outputValueUsd
+        refundAmount
+        refundValueUsd
+        networkFeeValueUsd
+        totalChunks
+        executedChunks
+        isDca
+        isBoosted
+        isOnChain
+        isInternal
+        isCcm
+        isVaultSwap
+        completedBlockId
+        completedBlockTimestamp
+        completedBlockDate
+        mainBrokerAccountSs58Id
+        mainBrokerFeeValueUsd
+        affiliateBroker1AccountSs58Id
+        affiliateBroker1FeeValueUsd
+        completedInSeconds
+        startedBlockDate
+        startedBlockId
+        startedBlockTimestamp
+        destinationAddress
+        outputAndIntermediateValueUsd
+        refundAddress
+        status
+        isInProgress
+        broker: accountByMainBrokerAccountSs58Id {
+            alias
+        }
+    }
+"#;
+
+// Client for the Chainflip reporting service GraphQL API
+#[derive(Clone)]
+pub struct ChainFlip {
+    client: Client,
+    base_url: String,
+    max_attempts: u32,
+    page_size: i32,
+}
 
 impl ChainFlip {
+    pub fn new(config: &ChainflipConfig) -> Result<Self, reqwest::Error> {
+        let client = Client::builder().timeout(config.timeout()).build()?;
+        Ok(Self {
+            client,
+            base_url: config.base_url.clone(),
+            max_attempts: config.max_attempts,
+            page_size: config.page_size,
+        })
+    }
+
+    pub fn page_size(&self) -> i32 {
+        self.page_size
+    }
+
     async fn fetch_with_retry(
-        url: &str,
-        client: &Client,
+        &self,
         _cursor: Option<&str>,
         query: &str,
         variables: serde_json::Value,
         operation_name: &str,
     ) -> Result<SwapResponse, Box<dyn std::error::Error + Send + Sync>> {
+        let url = self.base_url.as_str();
         let mut attempts = 0;
-        let max_attempts = 10;
+        let max_attempts = self.max_attempts;
 
         loop {
             attempts += 1;
@@ -27,7 +103,8 @@ impl ChainFlip {
                 "operationName": operation_name
             });
 
-            match client
+            match self
+                .client
                 .post(url)
                 .header("Content-Type", "application/json")
                 .json(&body)
@@ -59,14 +136,13 @@ impl ChainFlip {
     }
 
     pub async fn fetch_chainflip_swaps(
-        base_url: &str,
+        &self,
         first: Option<i32>,
         offset: Option<i32>,
         destination_address: Option<&str>,
     ) -> Result<SwapResponse, Box<dyn std::error::Error + Send + Sync>> {
-        let client = Client::builder().timeout(Duration::from_secs(15)).build()?;
-
-        let query = r#"
+        let query = [
+            r#"
             query GetAllSwaps($first: Int, $offset: Int, $destinationOrRefundAddress: String, $swapRequestNativeId: BigInt, $mainBrokerAccountSs58Id: String, $affiliateBrokerAccountSs58Id: String, $asset: ChainflipAsset, $isOnChain: Boolean, $lpRefundAddress: String, $alias: String) {
                 allSwapRequests(
                     orderBy: [IS_IN_PROGRESS_DESC, SWAP_REQUEST_NATIVE_ID_DESC]
@@ -81,67 +157,63 @@ impl ChainFlip {
                         hasNextPage
                         endCursor
                     }
-                    edges {
-                        node {
-                            id
-                            swapRequestNativeId
-                            sourceAsset
-                            destAsset
-                            baseAssetLeg1
-                            baseAssetLeg2
-                            ingressAmount
-                            ingressValueUsd
-                            egressAmount
-                            egressValueUsd
-                            inputAmount
-                            inputValueUsd
-                            intermediateAmount
-                            intermediateValueUsd
-                            outputAmount
-                            outputValueUsd
-                            refundAmount
-                            refundValueUsd
-                            networkFeeValueUsd
-                            totalChunks
-                            executedChunks
-                            isDca
-                            isBoosted
-                            isOnChain
-                            isInternal
-                            isCcm
-                            isVaultSwap
-                            completedBlockId
-                            completedBlockTimestamp
-                            completedBlockDate
-                            mainBrokerAccountSs58Id
-                            mainBrokerFeeValueUsd
-                            affiliateBroker1AccountSs58Id
-                            affiliateBroker1FeeValueUsd
-                            completedInSeconds
-                            startedBlockDate
-                            startedBlockId
-                            startedBlockTimestamp
-                            destinationAddress
-                            outputAndIntermediateValueUsd
-                            refundAddress
-                            status
-                            isInProgress
-                            broker: accountByMainBrokerAccountSs58Id {
-                                alias
-                            }
-                        }
+                    edges {"#,
+            SWAP_NODE_FIELDS,
+            r#"
                     }
                     totalCount
                 }
             }
-        "#;
+        "#,
+        ]
+        .concat();
 
         let variables = json!({
-            "first": first.unwrap_or(30),
+            "first": first.unwrap_or(self.page_size),
             "offset": offset.unwrap_or(0),
             "destinationOrRefundAddress": destination_address
         });
 
-        Self::fetch_with_retry(base_url, &client, None, query, variables, "GetAllSwaps").await
+        self.fetch_with_retry(None, &query, variables, "GetAllSwaps")
+            .await
+    }
+
+    pub async fn fetch_chainflip_swap_by_id(
+        &self,
+        swap_id: &str,
+    ) -> Result<Option<SwapNode>, Box<dyn std::error::Error + Send + Sync>> {
+        let query = [
+            r#"
+            query GetSwapById($swapRequestNativeId: BigInt!) {
+                allSwapRequests(condition: {swapRequestNativeId: $swapRequestNativeId}) {
+                    pageInfo {
+                        hasPreviousPage
+                        startCursor
+                        hasNextPage
+                        endCursor
+                    }
+                    edges {"#,
+            SWAP_NODE_FIELDS,
+            r#"
+                    }
+                    totalCount
+                }
+            }
+        "#,
+        ]
+        .concat();
+
+        let variables = json!({ "swapRequestNativeId": swap_id });
+
+        let resp = self
+            .fetch_with_retry(None, &query, variables, "GetSwapById")
+            .await?;
+        Ok(resp
+            .data
+            .allSwapRequests
+            .edges
+            .into_iter()
+            .next()
+            .map(|edge| edge.node))
     }
 }
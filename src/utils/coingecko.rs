@@ -1,31 +1,47 @@
-use crate::models::{CoinSearchResponse, PriceFetchResponse};
-use dotenv::dotenv;
-use once_cell::sync::Lazy;
+use crate::{
+    config::CoinGeckoConfig,
+    models::{CoinSearchResponse, MarketChartResponse, PriceFetchResponse},
+};
 use reqwest::{
     header::{HeaderMap, HeaderValue},
     Client, Error as ReqwestError,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::{collections::HashMap, env};
+use std::time::Duration;
+use thiserror::Error;
 use tokio::sync::RwLock;
 
+pub type SharedCoinGecko = Arc<RwLock<CoinGecko>>;
+
+#[derive(Debug, Error)]
+pub enum CoinGeckoError {
+    #[error("COINGECKO_API_KEY is not set")]
+    MissingApiKey,
+    #[error("COINGECKO_API_KEY is not a valid header value")]
+    InvalidApiKey,
+    #[error("Error building CoinGecko client: {0}")]
+    Client(#[from] ReqwestError),
+}
+
 pub struct CoinGecko {
     client: Client,
     base_url: String,
+    request_delay: Duration,
     coin_id: HashMap<String, String>,
 }
 
 impl CoinGecko {
-    pub fn init() -> Result<Self, ReqwestError> {
-        dotenv().ok();
-
-        let coingecko_base_url = env::var("COINGECKO_BASE_URL").expect("Coingecko BASE URL needed");
-        let coingecko_api_key = env::var("COINGECKO_API_KEY").expect("Coingecko API KEY needed");
+    pub fn init(config: &CoinGeckoConfig) -> Result<Self, CoinGeckoError> {
+        let coingecko_api_key = config
+            .api_key
+            .as_deref()
+            .ok_or(CoinGeckoError::MissingApiKey)?;
 
         let mut headers = HeaderMap::new();
         headers.insert(
             "x-cg-demo-api-key",
-            HeaderValue::from_str(&coingecko_api_key).expect("Invalid Header"),
+            HeaderValue::from_str(coingecko_api_key).map_err(|_| CoinGeckoError::InvalidApiKey)?,
         );
         headers.insert("Accept", HeaderValue::from_static("application/json"));
 
@@ -34,7 +50,8 @@ impl CoinGecko {
 
         Ok(Self {
             client,
-            base_url: coingecko_base_url,
+            base_url: config.base_url.clone(),
+            request_delay: config.request_delay(),
             coin_id,
         })
     }
@@ -49,18 +66,53 @@ impl CoinGecko {
         Ok(resp.market_data.current_price.usd)
     }
 
-    // Search for a coin by name
-    pub async fn search_coin(&self, coin_name: &str) -> Result<Option<String>, ReqwestError> {
-        let url = format!("{}/search?query={}", self.base_url, coin_name);
+    // USD price points between two unix timestamps as (milliseconds, price) pairs
+    pub async fn fetch_market_chart_range(
+        &self,
+        coin_id: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<(i64, f64)>, ReqwestError> {
+        let url = format!(
+            "{}/coins/{}/market_chart/range?vs_currency=usd&from={}&to={}",
+            self.base_url, coin_id, from, to
+        );
 
         let response = self.client.get(&url).send().await?.error_for_status()?;
+        let resp: MarketChartResponse = response.json().await?;
+
+        Ok(resp
+            .prices
+            .into_iter()
+            .map(|[timestamp, price]| (timestamp as i64, price))
+            .collect())
+    }
+
+    // Search for a coin by symbol. Only an exact symbol match is accepted, since the top
+    // result for an unknown symbol is usually an unrelated coin.
+    pub async fn search_coin(&self, coin_name: &str) -> Result<Option<String>, ReqwestError> {
+        let url = format!("{}/search", self.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .query(&[("query", coin_name)])
+            .send()
+            .await?
+            .error_for_status()?;
 
         let resp: CoinSearchResponse = response.json().await?;
 
-        Ok(match resp.coins.get(0) {
-            Some(coin) => Some(coin.id.clone()),
-            None => None,
-        })
+        Ok(resp
+            .coins
+            .into_iter()
+            .find(|coin| coin.symbol.eq_ignore_ascii_case(coin_name))
+            .map(|coin| coin.id))
+    }
+
+    // Callers sleep this long after each request to stay within the plan's rate limit
+    pub fn request_delay(&self) -> Duration {
+        self.request_delay
     }
 
     pub fn get_coin_id(&self, asset_name: &str) -> Option<String> {
@@ -71,10 +123,15 @@ impl CoinGecko {
         self.coin_id
             .insert(coin_name.to_string(), coin_id.to_string());
     }
-}
 
-pub static COINGECKO_INSTANCE: Lazy<Arc<RwLock<CoinGecko>>> = Lazy::new(|| {
-    Arc::new(RwLock::new(
-        CoinGecko::init().expect("Failed to initialize CoinGecko client"),
-    ))
-});
+    // None when CoinGecko isn't configured, in which case pricing falls back to the other providers
+    pub fn shared(config: &CoinGeckoConfig) -> Option<SharedCoinGecko> {
+        match Self::init(config) {
+            Ok(coingecko) => Some(Arc::new(RwLock::new(coingecko))),
+            Err(err) => {
+                println!("CoinGecko Disabled : {}", err);
+                None
+            }
+        }
+    }
+}
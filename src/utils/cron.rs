@@ -1,56 +1,87 @@
 use chrono::{DateTime, Duration, NaiveTime, Utc};
-use futures_util::lock::Mutex;
-use std::{collections::HashSet, sync::Arc};
 
 use crate::{
-    db::PostgreSQL,
+    config::{PricesConfig, SourceConfig},
+    db::{PostgreSQL, SWAP_HISTORY_TABLE},
     fetcher::{
-        fetch_btc_closing_price, fetch_daily_data, fetch_latest_data, retry_pending_transactions,
+        backfill_btc_closing_prices, fetch_asset_closing_prices, fetch_btc_closing_price,
+        fetch_daily_data, fetch_latest_data, fetch_price_candles, last_closed_date,
+        refresh_volume_rollups, retry_pending_transactions, value_thorchain_swaps,
+    },
+    utils::{
+        chainflip::ChainFlip,
+        coingecko::SharedCoinGecko,
+        midgard::MidGard,
+        pending_tracker::{PendingTracker, RetryPolicy},
+        price_provider::PriceProviderChain,
     },
-    SwapType, NATIVE_SWAPS_BASE_URL,
 };
 
-pub async fn start_cronjob(pg: PostgreSQL, base_url: &str, swap_type: SwapType) {
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+pub async fn start_cronjob(
+    pg: PostgreSQL,
+    midgard: MidGard,
+    pending_tracker: PendingTracker,
+    source: SourceConfig,
+) {
+    let mut interval =
+        tokio::time::interval(tokio::time::Duration::from_secs(source.poll_interval_secs));
     loop {
         interval.tick().await;
-        let swap_type_str = match swap_type {
-            SwapType::NATIVE => "Native Swaps",
-            SwapType::TRADE => "Trade Swaps",
-        };
-        println!("Fetching Latest {} Data", swap_type_str);
-        if let Err(e) = fetch_latest_data(&pg, base_url, swap_type.clone()).await {
-            println!("Error pulling latest {} data: {}", swap_type_str, e);
+        let source_label = source.label();
+        println!("Fetching Latest {} Data", source_label);
+        if let Err(e) = fetch_latest_data(&pg, &midgard, &pending_tracker, &source).await {
+            println!("Error pulling latest {} data: {}", source_label, e);
         }
     }
 }
 
 pub async fn start_retry(
     pg: PostgreSQL,
-    base_url: &str,
-    pending_ids: Arc<Mutex<HashSet<String>>>,
-    swap_type: SwapType,
+    midgard: MidGard,
+    pending_tracker: PendingTracker,
+    source: SourceConfig,
+    retry_policy: RetryPolicy,
 ) {
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(300));
+    let mut interval =
+        tokio::time::interval(tokio::time::Duration::from_secs(source.retry_interval_secs));
     loop {
         interval.tick().await;
-        let swap_type_str = match swap_type {
-            SwapType::NATIVE => "Native Swaps",
-            SwapType::TRADE => "Trade Swaps",
-        };
-        println!("Retrying Pending {} Transactions", swap_type_str);
+        let source_label = source.label();
+        println!("Retrying Pending {} Transactions", source_label);
         if let Err(e) =
-            retry_pending_transactions(&pg, base_url, pending_ids.clone(), swap_type.clone()).await
+            retry_pending_transactions(&pg, &midgard, &pending_tracker, &source, &retry_policy)
+                .await
         {
             println!(
                 "Error retrying pending {} transactions: {}",
-                swap_type_str, e
+                source_label, e
             );
         }
     }
 }
 
-pub async fn start_fetch_closing_price(pg: PostgreSQL) {
+pub async fn start_fetch_closing_price(
+    pg: PostgreSQL,
+    prices: PriceProviderChain,
+    config: PricesConfig,
+) {
+    let backfill_start = config.backfill_start();
+    println!("Backfilling BTC Closing Prices from {}", backfill_start);
+    match backfill_btc_closing_prices(&pg, &prices, backfill_start, last_closed_date()).await {
+        Ok(summary) => println!(
+            "Stored {} BTC Closing Prices, {} failed",
+            summary.stored, summary.failed
+        ),
+        Err(e) => println!("Error backfilling closing prices: {}", e),
+    }
+    match fetch_asset_closing_prices(&pg, &prices, backfill_start).await {
+        Ok(summary) => println!(
+            "Stored {} Asset Closing Prices, {} failed",
+            summary.stored, summary.failed
+        ),
+        Err(e) => println!("Error backfilling asset closing prices: {}", e),
+    }
+
     loop {
         let now: DateTime<Utc> = Utc::now();
         let next_run = {
@@ -67,13 +98,27 @@ pub async fn start_fetch_closing_price(pg: PostgreSQL) {
         tokio::time::sleep(delay.to_std().unwrap()).await;
 
         println!("Fetching BTC Price");
-        if let Err(e) = fetch_btc_closing_price(&pg).await {
+        if let Err(e) = fetch_btc_closing_price(&pg, &prices).await {
             println!("Error fetching closing price: {}", e);
         }
+
+        println!("Fetching Asset Closing Prices");
+        match fetch_asset_closing_prices(&pg, &prices, config.backfill_start()).await {
+            Ok(summary) => println!(
+                "Stored {} Asset Closing Prices, {} failed",
+                summary.stored, summary.failed
+            ),
+            Err(e) => println!("Error fetching asset closing prices: {}", e),
+        }
     }
 }
 
-pub async fn start_daily_fetch(pg: PostgreSQL) {
+pub async fn start_daily_fetch(
+    pg: PostgreSQL,
+    midgard: MidGard,
+    pending_tracker: PendingTracker,
+    source: SourceConfig,
+) {
     loop {
         let now: DateTime<Utc> = Utc::now();
         let next_run = {
@@ -100,31 +145,102 @@ pub async fn start_daily_fetch(pg: PostgreSQL) {
         let epoch_timestamp = start_of_period.and_utc().timestamp();
 
         println!(
-            "Running reconcile fetch job with epoch: {}",
+            "Running reconcile fetch job for {} with epoch: {}",
+            source.label(),
             epoch_timestamp
         );
-        if let Err(e) = fetch_daily_data(
-            &pg,
-            &NATIVE_SWAPS_BASE_URL,
-            SwapType::NATIVE,
-            epoch_timestamp,
-        )
-        .await
+        if let Err(e) =
+            fetch_daily_data(&pg, &midgard, &pending_tracker, &source, epoch_timestamp).await
         {
             println!("Error in reconcile fetch job: {}", e);
         }
     }
 }
 
-pub async fn start_chainflip_swaps_incremental(pg: PostgreSQL, base_url: &str) {
+pub async fn start_price_candles(
+    pg: PostgreSQL,
+    coingecko: Option<SharedCoinGecko>,
+    interval_secs: u64,
+) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+    loop {
+        interval.tick().await;
+        println!("Fetching Hourly Price Candles");
+        match fetch_price_candles(&pg, coingecko.as_ref()).await {
+            Ok(summary) => println!(
+                "Stored {} Price Candles, {} failed",
+                summary.stored, summary.failed
+            ),
+            Err(e) => println!("Error fetching price candles: {}", e),
+        }
+    }
+}
+
+// Values THORChain swaps in every source table and btc_user_data against btc_closing_prices,
+// backfilling any missing dates first
+pub async fn start_swap_valuation(
+    pg: PostgreSQL,
+    prices: PriceProviderChain,
+    sources: Vec<SourceConfig>,
+    interval_secs: u64,
+) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+    loop {
+        interval.tick().await;
+        let tables = sources
+            .iter()
+            .map(|source| (source.table.as_str(), source.label()))
+            .chain(std::iter::once((
+                SWAP_HISTORY_TABLE,
+                SWAP_HISTORY_TABLE.to_string(),
+            )));
+        let mut earliest_date = None;
+        for (table, label) in tables {
+            println!("Valuing {} Swaps", label);
+            match value_thorchain_swaps(&pg, &prices, table, &label).await {
+                Ok(summary) => {
+                    earliest_date = earliest_date.into_iter().chain(summary.earliest_date).min()
+                }
+                Err(e) => println!("Error valuing {} swaps: {}", label, e),
+            }
+        }
+        // Newly valued swaps change btc_volume_usd for days the rollup window may not reach
+        if let Some(since) = earliest_date {
+            if let Err(e) = refresh_volume_rollups(&pg, Some(since)).await {
+                println!("Error refreshing volume rollups: {}", e);
+            }
+        }
+    }
+}
+
+// Rebuilds all rollups on startup, then refreshes the trailing window every interval so swaps
+// that settle late or arrive with the daily fetch are counted. Backfill, reconcile and the
+// valuation job refresh the older days they touch themselves.
+pub async fn start_volume_rollup(pg: PostgreSQL, interval_secs: u64, window_days: i64) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
+    let mut since = None;
+    loop {
+        interval.tick().await;
+        match refresh_volume_rollups(&pg, since).await {
+            Ok(()) => since = Some(Utc::now().date_naive() - Duration::days(window_days)),
+            Err(e) => println!("Error refreshing volume rollups: {}", e),
+        }
+    }
+}
+
+pub async fn start_chainflip_swaps_incremental(
+    pg: PostgreSQL,
+    chainflip: ChainFlip,
+    interval_secs: u64,
+) {
     println!("STARTING PERIODIC CHAINFLIP SWAPS FETCHING");
-    println!("Will run every 15 minutes (900 seconds)");
+    println!("Will run every {} seconds", interval_secs);
 
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(900)); // 15 minutes
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs));
     loop {
         interval.tick().await;
         println!("\nPERIODIC UPDATE: Fetching recent Chainflip Swaps");
-        if let Err(e) = crate::fetcher::fetch_chainflip_swaps_incremental(base_url, &pg).await {
+        if let Err(e) = crate::fetcher::fetch_chainflip_swaps_incremental(&chainflip, &pg).await {
             println!("ERROR: Periodic Chainflip swaps update failed: {}", e);
         } else {
             println!("Periodic update completed");
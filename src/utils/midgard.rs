@@ -1,24 +1,43 @@
-use crate::{models::actions_model::ActionsFetchResponse, RATE_LIMIT_DELAY, REQUEST_SEMAPHORE};
+use crate::{config::MidgardConfig, models::actions_model::ActionsFetchResponse, REQUEST_SEMAPHORE};
 use reqwest::Client;
 use std::time::Duration;
 
-pub struct MidGard;
+// Shared Midgard client; sync requests are serialized through REQUEST_SEMAPHORE and rate limited
+#[derive(Clone)]
+pub struct MidGard {
+    client: Client,
+    max_attempts: u32,
+    rate_limit_delay: Duration,
+    lookup_timeout: Duration,
+}
+
 impl MidGard {
+    pub fn new(config: &MidgardConfig) -> Result<Self, reqwest::Error> {
+        let client = Client::builder().timeout(config.timeout()).build()?;
+        Ok(Self {
+            client,
+            max_attempts: config.max_attempts,
+            rate_limit_delay: config.rate_limit_delay(),
+            lookup_timeout: config.lookup_timeout(),
+        })
+    }
+
     async fn fetch_with_retry(
+        &self,
         url: &str,
-        client: &Client,
+        query: &[(&str, &str)],
     ) -> Result<ActionsFetchResponse, reqwest::Error> {
         let mut attempts = 0;
-        let max_attempts = 10;
+        let max_attempts = self.max_attempts;
 
         loop {
             attempts += 1;
             println!("Fetching URL (Attempt {}): {}", attempts, url);
 
             let _permit = REQUEST_SEMAPHORE.acquire().await.unwrap();
-            tokio::time::sleep(*RATE_LIMIT_DELAY).await;
+            tokio::time::sleep(self.rate_limit_delay).await;
 
-            let response = client.get(url).send().await;
+            let response = self.client.get(url).query(query).send().await;
 
             match response {
                 Ok(resp) => {
@@ -44,10 +63,10 @@ impl MidGard {
     }
 
     pub async fn fetch_actions_with_nextpage(
+        &self,
         base_url: &str,
         next_page_token: &str,
     ) -> Result<ActionsFetchResponse, reqwest::Error> {
-        let client = Client::builder().timeout(Duration::from_secs(15)).build()?;
         let url = if next_page_token.is_empty() {
             base_url.to_string()
         } else {
@@ -56,45 +75,71 @@ impl MidGard {
                 base_url,next_page_token
             )
         };
-        Self::fetch_with_retry(&url, &client).await
+        self.fetch_with_retry(&url, &[]).await
     }
 
     pub async fn fetch_actions_with_prevpage(
+        &self,
         base_url: &str,
         prev_page_token: &str,
     ) -> Result<ActionsFetchResponse, reqwest::Error> {
-        let client = Client::builder().timeout(Duration::from_secs(15)).build()?;
         let url = format!(
             "{}&prevPageToken={}",
             base_url,
             prev_page_token
         );
-        Self::fetch_with_retry(&url, &client).await
+        self.fetch_with_retry(&url, &[]).await
+    }
+
+    // Actions at or before `timestamp`, newest first
+    pub async fn fetch_actions_until(
+        &self,
+        base_url: &str,
+        timestamp: &str,
+    ) -> Result<ActionsFetchResponse, reqwest::Error> {
+        let url = format!(
+            "{}&timestamp={}",
+            base_url,
+            timestamp
+        );
+        self.fetch_with_retry(&url, &[]).await
     }
 
     pub async fn fetch_actions_with_timestamp(
+        &self,
         base_url: &str,
         timestamp: &str,
     ) -> Result<ActionsFetchResponse, reqwest::Error> {
-        let client = Client::builder().timeout(Duration::from_secs(15)).build()?;
         let url = format!(
             "{}&fromTimestamp={}",
             base_url,
             timestamp
         );
-        Self::fetch_with_retry(&url, &client).await
+        self.fetch_with_retry(&url, &[]).await
     }
 
     pub async fn fetch_action_with_transactionid(
+        &self,
         base_url: &str,
         tx_id: String,
     ) -> Result<ActionsFetchResponse, reqwest::Error> {
-        let client = Client::builder().timeout(Duration::from_secs(15)).build()?;
-        let url = format!(
-            "{}?txid={}",
-            base_url,
-            tx_id
-        );
-        Self::fetch_with_retry(&url, &client).await
+        self.fetch_with_retry(base_url, &[("txid", &tx_id)]).await
+    }
+
+    // Single attempt with a short timeout for request handlers; skips the sync's semaphore and
+    // rate limit delay so a lookup never queues behind a backfill
+    pub async fn lookup_action_with_transactionid(
+        &self,
+        base_url: &str,
+        tx_id: &str,
+    ) -> Result<ActionsFetchResponse, reqwest::Error> {
+        self.client
+            .get(base_url)
+            .query(&[("txid", tx_id)])
+            .timeout(self.lookup_timeout)
+            .send()
+            .await?
+            .json::<ActionsFetchResponse>()
+            .await
     }
 }
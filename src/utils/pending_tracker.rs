@@ -0,0 +1,180 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::Error as SqlxError;
+use std::sync::Arc;
+
+use crate::{
+    config::{PendingConfig, SourceConfig},
+    db::PostgreSQL,
+    models::pending_swaps::PendingSwap,
+};
+
+// Decides when a pending swap should stop being retried and move to the dead-letter table
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: i32,
+    pub max_age: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(&PendingConfig::default())
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(config: &PendingConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts,
+            max_age: Duration::hours(config.max_age_hours),
+        }
+    }
+
+    pub fn expiry_reason(&self, pending: &PendingSwap, now: DateTime<Utc>) -> Option<String> {
+        if pending.attempt_count >= self.max_attempts {
+            return Some(format!(
+                "Exceeded max attempts ({}/{})",
+                pending.attempt_count, self.max_attempts
+            ));
+        }
+        let age = now - pending.first_seen_at;
+        if age >= self.max_age {
+            return Some(format!(
+                "Exceeded max age ({}h/{}h)",
+                age.num_hours(),
+                self.max_age.num_hours()
+            ));
+        }
+        None
+    }
+}
+
+// Where pending and dead-lettered swaps are kept. Postgres is the only production store.
+#[async_trait]
+pub trait PendingStore: Send + Sync {
+    async fn insert(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError>;
+
+    async fn fetch(&self, source: &SourceConfig) -> Result<Vec<PendingSwap>, SqlxError>;
+
+    async fn record_attempt(
+        &self,
+        tx_id: &str,
+        source: &SourceConfig,
+        error: Option<&str>,
+    ) -> Result<(), SqlxError>;
+
+    async fn delete(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError>;
+
+    async fn move_to_dead_letter(
+        &self,
+        tx_id: &str,
+        source: &SourceConfig,
+        reason: &str,
+    ) -> Result<(), SqlxError>;
+}
+
+#[async_trait]
+impl PendingStore for PostgreSQL {
+    async fn insert(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError> {
+        self.insert_pending_swap(tx_id, &source.name, source.swap_type.as_str())
+            .await
+    }
+
+    async fn fetch(&self, source: &SourceConfig) -> Result<Vec<PendingSwap>, SqlxError> {
+        self.fetch_pending_swaps(&source.name).await
+    }
+
+    async fn record_attempt(
+        &self,
+        tx_id: &str,
+        source: &SourceConfig,
+        error: Option<&str>,
+    ) -> Result<(), SqlxError> {
+        self.record_pending_swap_attempt(tx_id, &source.name, error)
+            .await
+    }
+
+    async fn delete(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError> {
+        self.delete_pending_swap(tx_id, &source.name).await
+    }
+
+    async fn move_to_dead_letter(
+        &self,
+        tx_id: &str,
+        source: &SourceConfig,
+        reason: &str,
+    ) -> Result<(), SqlxError> {
+        self.move_pending_swap_to_dead_letter(tx_id, &source.name, reason)
+            .await
+    }
+}
+
+// Shared record of swaps that were seen before they settled. The transaction handler
+// writes into it while processing batches and the retry job reads from the same store.
+#[derive(Clone)]
+pub struct PendingTracker {
+    store: Arc<dyn PendingStore>,
+}
+
+impl PendingTracker {
+    pub fn new(store: Arc<dyn PendingStore>) -> Self {
+        Self { store }
+    }
+
+    pub fn postgres(pg: PostgreSQL) -> Self {
+        Self::new(Arc::new(pg))
+    }
+
+    pub async fn track(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError> {
+        self.store.insert(tx_id, source).await
+    }
+
+    pub async fn pending(&self, source: &SourceConfig) -> Result<Vec<PendingSwap>, SqlxError> {
+        self.store.fetch(source).await
+    }
+
+    // Swaps the retry job should re-fetch; expired ones are moved to the dead-letter list
+    pub async fn due_for_retry(
+        &self,
+        source: &SourceConfig,
+        retry_policy: &RetryPolicy,
+    ) -> Result<Vec<PendingSwap>, SqlxError> {
+        let now = Utc::now();
+        let mut due = Vec::new();
+        for pending in self.pending(source).await? {
+            match retry_policy.expiry_reason(&pending, now) {
+                Some(reason) => {
+                    self.abandon(&pending.tx_id, source, &reason).await?;
+                    println!(
+                        "Pending Transaction Abandoned : {} ({})",
+                        &pending.tx_id, reason
+                    );
+                }
+                None => due.push(pending),
+            }
+        }
+        Ok(due)
+    }
+
+    pub async fn record_attempt(
+        &self,
+        tx_id: &str,
+        source: &SourceConfig,
+        error: Option<&str>,
+    ) -> Result<(), SqlxError> {
+        self.store.record_attempt(tx_id, source, error).await
+    }
+
+    pub async fn settle(&self, tx_id: &str, source: &SourceConfig) -> Result<(), SqlxError> {
+        self.store.delete(tx_id, source).await
+    }
+
+    pub async fn abandon(
+        &self,
+        tx_id: &str,
+        source: &SourceConfig,
+        reason: &str,
+    ) -> Result<(), SqlxError> {
+        self.store.move_to_dead_letter(tx_id, source, reason).await
+    }
+}
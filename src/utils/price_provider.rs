@@ -0,0 +1,321 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::Error as ReqwestError;
+use sqlx::Error as SqlxError;
+use std::{sync::Arc, time::Duration};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+use crate::{
+    config::{PricesConfig, SourceConfig},
+    db::PostgreSQL,
+    utils::{
+        coingecko::{CoinGecko, SharedCoinGecko},
+        price_symbol,
+    },
+};
+
+const COINGECKO_BACKOFF: Duration = Duration::from_secs(60);
+const COINGECKO_MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Error)]
+pub enum PriceError {
+    #[error("{0} is not configured")]
+    Unavailable(&'static str),
+    #[error("{provider} has no {symbol} price for {date}")]
+    NotFound {
+        provider: &'static str,
+        symbol: String,
+        date: NaiveDate,
+    },
+    #[error("Request error: {0}")]
+    Request(#[from] ReqwestError),
+    #[error("Database error: {0}")]
+    Database(#[from] SqlxError),
+    #[error("No provider has a {symbol} price for {date}")]
+    Exhausted { symbol: String, date: NaiveDate },
+}
+
+// A day's USD close along with the provider that supplied it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvidedPrice {
+    pub price_usd: f64,
+    pub provider: &'static str,
+    pub coin_id: Option<String>,
+}
+
+// Source of daily USD closes keyed by the symbol from utils::price_symbol
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn closing_price(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<ProvidedPrice, PriceError>;
+}
+
+// Tries each provider in order and returns the first price found
+#[derive(Clone)]
+pub struct PriceProviderChain {
+    providers: Vec<Arc<dyn PriceProvider>>,
+}
+
+impl PriceProviderChain {
+    pub fn new(providers: Vec<Arc<dyn PriceProvider>>) -> Self {
+        Self { providers }
+    }
+
+    // Providers named in prices.providers, in order; names are checked by Config::validate
+    pub fn from_config(
+        config: &PricesConfig,
+        pg: &PostgreSQL,
+        coingecko: Option<SharedCoinGecko>,
+        sources: &[SourceConfig],
+    ) -> Self {
+        let mut providers: Vec<Arc<dyn PriceProvider>> = Vec::new();
+        for name in &config.providers {
+            match name.as_str() {
+                "coingecko" => providers.push(Arc::new(CoinGeckoPriceProvider::new(
+                    pg.clone(),
+                    coingecko.clone(),
+                ))),
+                "midgard" => providers.push(Arc::new(MidgardPriceProvider::new(
+                    pg.clone(),
+                    sources.iter().map(|source| source.table.clone()).collect(),
+                ))),
+                "chainflip" => providers.push(Arc::new(ChainflipPriceProvider::new(pg.clone()))),
+                _ => println!("Unknown Price Provider {}, skipping", name),
+            }
+        }
+        Self::new(providers)
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.providers
+            .iter()
+            .map(|provider| provider.name())
+            .collect()
+    }
+
+    pub async fn closing_price(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<ProvidedPrice, PriceError> {
+        for provider in &self.providers {
+            match provider.closing_price(symbol, date).await {
+                Ok(price) => return Ok(price),
+                Err(err) => println!(
+                    "{} Price for {} on {} Unavailable: {}",
+                    provider.name(),
+                    symbol,
+                    date,
+                    err
+                ),
+            }
+        }
+        Err(PriceError::Exhausted {
+            symbol: symbol.to_string(),
+            date,
+        })
+    }
+}
+
+// Latest price among `legs` (asset, price, timestamp) whose asset maps to `symbol`
+pub fn latest_leg_price(symbol: &str, legs: &[(String, f64, i64)]) -> Option<(f64, i64)> {
+    legs.iter()
+        .filter(|(asset, price, _)| {
+            price.is_finite() && price_symbol(asset).as_deref() == Some(symbol)
+        })
+        .max_by_key(|(_, _, timestamp)| *timestamp)
+        .map(|(_, price, timestamp)| (*price, *timestamp))
+}
+
+pub struct CoinGeckoPriceProvider {
+    pg: PostgreSQL,
+    coingecko: Option<SharedCoinGecko>,
+}
+
+impl CoinGeckoPriceProvider {
+    pub fn new(pg: PostgreSQL, coingecko: Option<SharedCoinGecko>) -> Self {
+        Self { pg, coingecko }
+    }
+
+    // Looks the symbol up in the in-memory cache, then coingecko_coin_ids, then CoinGecko
+    // search. Search results, including misses, are persisted so each symbol is searched once.
+    async fn resolve_coin_id(
+        &self,
+        coingecko: &RwLock<CoinGecko>,
+        symbol: &str,
+    ) -> Result<Option<String>, PriceError> {
+        if let Some(coin_id) = coingecko.read().await.get_coin_id(symbol) {
+            return Ok(Some(coin_id));
+        }
+
+        let coin_id = match self.pg.fetch_coin_id(symbol).await? {
+            Some(coin_id) => coin_id,
+            None => {
+                let coin_id = coingecko.read().await.search_coin(symbol).await?;
+                self.pg.upsert_coin_id(symbol, coin_id.as_deref()).await?;
+                let delay = coingecko.read().await.request_delay();
+                tokio::time::sleep(delay).await;
+                coin_id
+            }
+        };
+        if let Some(coin_id) = &coin_id {
+            coingecko.write().await.add_coin_id(symbol, coin_id);
+        }
+        Ok(coin_id)
+    }
+}
+
+#[async_trait]
+impl PriceProvider for CoinGeckoPriceProvider {
+    fn name(&self) -> &'static str {
+        "coingecko"
+    }
+
+    // CoinGecko's /history snapshot is taken at 00:00 UTC, so a day's close is the next day's
+    // snapshot. Failed fetches, usually rate limiting, are retried after a backoff.
+    async fn closing_price(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<ProvidedPrice, PriceError> {
+        let coingecko = self
+            .coingecko
+            .as_ref()
+            .ok_or(PriceError::Unavailable(self.name()))?;
+        let coin_id = match self.resolve_coin_id(coingecko, symbol).await? {
+            Some(coin_id) => coin_id,
+            None => {
+                return Err(PriceError::NotFound {
+                    provider: self.name(),
+                    symbol: symbol.to_string(),
+                    date,
+                })
+            }
+        };
+
+        let coingecko_date = (date + chrono::Duration::days(1))
+            .format("%d-%m-%Y")
+            .to_string();
+        let delay = coingecko.read().await.request_delay();
+        let mut attempts = 0;
+        let price = loop {
+            attempts += 1;
+            let result = coingecko
+                .read()
+                .await
+                .fetch_usd_price(&coin_id, &coingecko_date)
+                .await;
+            tokio::time::sleep(delay).await;
+            match result {
+                Ok(price) => break price,
+                Err(err) if attempts < COINGECKO_MAX_ATTEMPTS => {
+                    println!(
+                        "Error Fetching {} Price for {} (Attempt {}): {:?}",
+                        coin_id, date, attempts, err
+                    );
+                    tokio::time::sleep(COINGECKO_BACKOFF).await;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        };
+
+        Ok(ProvidedPrice {
+            price_usd: price,
+            provider: self.name(),
+            coin_id: Some(coin_id),
+        })
+    }
+}
+
+// Prices from the inPriceUSD/outPriceUSD Midgard reports with each swap, taken from the last
+// swap of the day in the configured THORChain tables
+pub struct MidgardPriceProvider {
+    pg: PostgreSQL,
+    tables: Vec<String>,
+}
+
+impl MidgardPriceProvider {
+    pub fn new(pg: PostgreSQL, tables: Vec<String>) -> Self {
+        Self { pg, tables }
+    }
+}
+
+#[async_trait]
+impl PriceProvider for MidgardPriceProvider {
+    fn name(&self) -> &'static str {
+        "midgard"
+    }
+
+    async fn closing_price(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<ProvidedPrice, PriceError> {
+        let mut latest: Option<(f64, i64)> = None;
+        for table in &self.tables {
+            let legs = self.pg.fetch_thorchain_leg_prices(table, date).await?;
+            if let Some((price, timestamp)) = latest_leg_price(symbol, &legs) {
+                if latest.is_none_or(|(_, latest_timestamp)| timestamp > latest_timestamp) {
+                    latest = Some((price, timestamp));
+                }
+            }
+        }
+
+        match latest {
+            Some((price, _)) => Ok(ProvidedPrice {
+                price_usd: price,
+                provider: self.name(),
+                coin_id: None,
+            }),
+            None => Err(PriceError::NotFound {
+                provider: self.name(),
+                symbol: symbol.to_string(),
+                date,
+            }),
+        }
+    }
+}
+
+// Prices from the inputValueUsd/outputValueUsd of the day's last Chainflip swap
+pub struct ChainflipPriceProvider {
+    pg: PostgreSQL,
+}
+
+impl ChainflipPriceProvider {
+    pub fn new(pg: PostgreSQL) -> Self {
+        Self { pg }
+    }
+}
+
+#[async_trait]
+impl PriceProvider for ChainflipPriceProvider {
+    fn name(&self) -> &'static str {
+        "chainflip"
+    }
+
+    async fn closing_price(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+    ) -> Result<ProvidedPrice, PriceError> {
+        let legs = self.pg.fetch_chainflip_leg_prices(date).await?;
+        match latest_leg_price(symbol, &legs) {
+            Some((price, _)) => Ok(ProvidedPrice {
+                price_usd: price,
+                provider: self.name(),
+                coin_id: None,
+            }),
+            None => Err(PriceError::NotFound {
+                provider: self.name(),
+                symbol: symbol.to_string(),
+                date,
+            }),
+        }
+    }
+}
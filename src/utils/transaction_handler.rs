@@ -1,29 +1,20 @@
 // use super::{calculate_transaction_amount, coingecko::COINGECKO_INSTANCE};
 use crate::{
-    db::PostgreSQL,
+    config::SourceConfig,
+    db::{InsertSummary, PostgreSQL},
     models::actions_model::{SwapTransaction, SwapTransactionFromatted, TransactionData},
-    utils::{convert_nano_to_sec, convert_to_standard_unit, format_epoch_timestamp, parse_f64},
-    SwapType,
+    utils::{
+        convert_nano_to_sec, convert_to_standard_unit, format_epoch_timestamp, parse_f64,
+        pending_tracker::PendingTracker, price_provider::PriceError,
+    },
 };
-use lazy_static::lazy_static;
 use reqwest::Error as ReqwestError;
+use serde_json::json;
 use sqlx::Error as SqlxError;
-use std::collections::HashSet;
 use std::fmt;
-use std::sync::Arc;
-use tokio::sync::Mutex;
 
 use super::asset_name_from_trade_pool;
 
-lazy_static! {
-    pub static ref TRADE_SWAPS_PENDING_IDS: Arc<Mutex<HashSet<String>>> =
-        Arc::new(Mutex::new(HashSet::new()));
-}
-lazy_static! {
-    pub static ref NATIVE_SWAPS_PENDING_IDS: Arc<Mutex<HashSet<String>>> =
-        Arc::new(Mutex::new(HashSet::new()));
-}
-
 #[derive(Debug)]
 pub enum TransactionError {
     MissingInCoin,
@@ -61,6 +52,12 @@ impl fmt::Display for TransactionError {
     }
 }
 
+impl From<PriceError> for TransactionError {
+    fn from(err: PriceError) -> Self {
+        TransactionError::PriceFetchError(err.to_string())
+    }
+}
+
 impl From<ReqwestError> for TransactionError {
     fn from(err: ReqwestError) -> Self {
         TransactionError::PriceFetchError(err.to_string())
@@ -73,9 +70,15 @@ impl From<SqlxError> for TransactionError {
     }
 }
 
-pub struct TransactionHandler;
+pub struct TransactionHandler {
+    pending_tracker: PendingTracker,
+}
 
 impl TransactionHandler {
+    pub fn new(pending_tracker: PendingTracker) -> Self {
+        Self { pending_tracker }
+    }
+
     pub async fn parse_data(
         &self,
         info: &TransactionData,
@@ -103,19 +106,18 @@ impl TransactionHandler {
             .get(0)
             .and_then(|data| data.txID.clone())
             .ok_or(TransactionError::MissingTxId)?;
-        let handler = TransactionHandler;
         let in_data = swap.in_data.get(0).ok_or(TransactionError::MissingInData)?;
-        let (in_asset, in_amount, in_address) = handler.parse_data(in_data).await?;
+        let (in_asset, in_amount, in_address) = self.parse_data(in_data).await?;
 
         let mut out_data = swap.out_data.clone();
         out_data.reverse();
 
         let out_data_1 = out_data.get(0).ok_or(TransactionError::MissingOutData)?;
-        let (asset_1, amount_1, address_1) = handler.parse_data(out_data_1).await?;
+        let (asset_1, amount_1, address_1) = self.parse_data(out_data_1).await?;
 
         let (out_asset_1, out_amount_1, out_address_1, out_asset_2, out_amount_2, out_address_2) =
             if let Some(out_data_2) = out_data.get(1) {
-                let (asset_2, amount_2, address_2) = handler.parse_data(out_data_2).await?;
+                let (asset_2, amount_2, address_2) = self.parse_data(out_data_2).await?;
 
                 if asset_1 == "THOR.RUNE" {
                     (
@@ -149,6 +151,31 @@ impl TransactionHandler {
                 (asset_1, amount_1, address_1, None, None, None)
             };
 
+        let swap_meta = &swap.metadata.swap;
+        let network_fees = if swap_meta.networkFees.is_empty() {
+            None
+        } else {
+            let fees: Vec<serde_json::Value> = swap_meta
+                .networkFees
+                .iter()
+                .map(|fee| {
+                    let amount = parse_f64(&fee.amount).unwrap_or(0.0);
+                    json!({
+                        "asset": fee.asset,
+                        "amount": convert_to_standard_unit(amount, 8),
+                    })
+                })
+                .collect();
+            Some(serde_json::Value::Array(fees).to_string())
+        };
+        let streaming_meta = swap_meta.streamingSwapMeta.clone().unwrap_or_default();
+        let in_amount_usd = parse_f64(&swap_meta.inPriceUSD)
+            .ok()
+            .map(|price| in_amount * price);
+        let out_amount_usd = parse_f64(&swap_meta.outPriceUSD)
+            .ok()
+            .map(|price| out_amount_1 * price);
+
         Ok(SwapTransactionFromatted {
             timestamp: epoc_timestamp,
             date: swap_date,
@@ -164,36 +191,60 @@ impl TransactionHandler {
             out_amount_2,
             out_address_2,
             status: swap.status.clone(),
+            in_amount_usd,
+            out_amount_usd,
+            network_fees,
+            liquidity_fee: swap_meta
+                .liquidityFee
+                .as_deref()
+                .and_then(|fee| parse_f64(fee).ok())
+                .map(|fee| convert_to_standard_unit(fee, 8)),
+            swap_slip_bps: swap_meta
+                .swapSlip
+                .as_deref()
+                .and_then(|slip| slip.parse::<i64>().ok()),
+            affiliate_fee_bps: swap_meta
+                .affiliateFee
+                .as_deref()
+                .and_then(|fee| fee.parse::<i64>().ok()),
+            affiliate_address: swap_meta
+                .affiliateAddress
+                .clone()
+                .filter(|address| !address.is_empty()),
+            memo: swap_meta.memo.clone(),
+            is_streaming_swap: swap_meta.isStreamingSwap,
+            streaming_quantity: streaming_meta
+                .quantity
+                .as_deref()
+                .and_then(|quantity| quantity.parse::<i64>().ok()),
+            streaming_interval: streaming_meta
+                .interval
+                .as_deref()
+                .and_then(|interval| interval.parse::<i64>().ok()),
+            btc_price_usd: None,
+            btc_value_usd: None,
         })
     }
 
+    // Settled swaps in `actions` are inserted in a single batch; pending ones are tracked for retry
     pub async fn process_and_insert_transaction(
         &self,
         pg: &PostgreSQL,
         actions: &Vec<SwapTransaction>,
-        swap_type: SwapType,
-    ) -> Result<(), TransactionError> {
-        let processed_transactions = self
-            .process_transactions(actions, swap_type.clone())
-            .await
-            .unwrap();
-        let table_name = match swap_type {
-            SwapType::NATIVE => "native_swaps_thorchain",
-            SwapType::TRADE => "swap_history_test",
-        };
+        source: &SourceConfig,
+    ) -> Result<InsertSummary, TransactionError> {
+        let processed_transactions = self.process_transactions(actions, source).await?;
 
-        for swap in processed_transactions {
-            if let Err(err) = pg.insert_new_record(swap.clone(), table_name).await {
-                println!("Error during insertion: {:?}", err);
-            }
-        }
-        Ok(())
+        let summary = pg
+            .insert_bulk(&source.table, processed_transactions)
+            .await?;
+        Ok(summary)
     }
 
     pub async fn process_transactions(
         &self,
         actions: &Vec<SwapTransaction>,
-        swap_type: SwapType,
+        source: &SourceConfig,
     ) -> Result<Vec<SwapTransactionFromatted>, TransactionError> {
         let mut result: Vec<SwapTransactionFromatted> = Vec::new();
         let mut pending_count = 0;
@@ -207,7 +258,7 @@ impl TransactionHandler {
                 }
             };
             if transaction_info.status != "success" {
-                self.track_pending_transaction(transaction_info.tx_id, swap_type.clone())
+                self.track_pending_transaction(&transaction_info.tx_id, source)
                     .await;
                 pending_count += 1;
             } else {
@@ -218,11 +269,12 @@ impl TransactionHandler {
         Ok(result)
     }
 
-    pub async fn track_pending_transaction(&self, transaction_id: String, swap_type: SwapType) {
-        let mut pending_txn_ids = match swap_type {
-            SwapType::NATIVE => NATIVE_SWAPS_PENDING_IDS.lock().await,
-            SwapType::TRADE => TRADE_SWAPS_PENDING_IDS.lock().await,
-        };
-        pending_txn_ids.insert(transaction_id);
+    pub async fn track_pending_transaction(&self, transaction_id: &str, source: &SourceConfig) {
+        if let Err(err) = self.pending_tracker.track(transaction_id, source).await {
+            println!(
+                "Error tracking pending transaction {}: {:?}",
+                transaction_id, err
+            );
+        }
     }
 }